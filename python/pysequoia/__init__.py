@@ -1,8 +1,14 @@
 import sys
 
 from .pysequoia import *  # noqa: F403
+from .pysequoia import autocrypt as autocrypt
+from .pysequoia import dane as dane
 from .pysequoia import packet as packet
+from .pysequoia import wkd as wkd
 
-# Register the native submodule so that "from pysequoia.packet import ..." works.
+# Register the native submodules so that "from pysequoia.packet import ..." works.
 # Without this, Python would look for pysequoia/packet.py which doesn't exist.
+sys.modules[__name__ + ".autocrypt"] = autocrypt
+sys.modules[__name__ + ".dane"] = dane
 sys.modules[__name__ + ".packet"] = packet
+sys.modules[__name__ + ".wkd"] = wkd
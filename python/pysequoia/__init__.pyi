@@ -1,9 +1,31 @@
-from .packet import HashAlgorithm, Packet, PublicKeyAlgorithm, SignatureType
+from .packet import DataFormat, HashAlgorithm, Packet, PublicKeyAlgorithm, SignatureType
 from collections.abc import Sequence
 from datetime import datetime, timedelta
 from os import PathLike
 from typing import Any, Final, final
 
+@final
+class AEADAlgorithm:
+    """
+    The AEAD mode used to protect message data (SEIPDv2, RFC 9580).
+    """
+    EAX: Final[AEADAlgorithm]
+    """
+    EAX mode
+    """
+    GCM: Final[AEADAlgorithm]
+    """
+    GCM mode
+    """
+    OCB: Final[AEADAlgorithm]
+    """
+    OCB mode, mandatory to implement
+    """
+    def __eq__(self, value: object, /) -> bool: ...
+    def __int__(self, /) -> int: ...
+    def __ne__(self, value: object, /) -> bool: ...
+    def __repr__(self, /) -> str: ...
+
 @final
 class ArmorKind:
     """
@@ -202,6 +224,28 @@ class CipherSuite:
     def __ne__(self, value: object, /) -> bool: ...
     def __repr__(self, /) -> str: ...
 
+@final
+class CompressionAlgorithm:
+    """
+    The compression algorithm used for message data.
+    """
+    BZip2: Final[CompressionAlgorithm]
+    """
+    BZip2, requires the `compression-bzip2` cargo feature
+    """
+    Zip: Final[CompressionAlgorithm]
+    """
+    DEFLATE (RFC 1951)
+    """
+    Zlib: Final[CompressionAlgorithm]
+    """
+    DEFLATE with ZLIB framing (RFC 1950)
+    """
+    def __eq__(self, value: object, /) -> bool: ...
+    def __int__(self, /) -> int: ...
+    def __ne__(self, value: object, /) -> bool: ...
+    def __repr__(self, /) -> str: ...
+
 @final
 class Decrypted:
     """
@@ -210,14 +254,76 @@ class Decrypted:
     Contains the decrypted/verified content (if available) and any valid signatures found.
     """
     @property
+    def aead(self, /) -> AEADAlgorithm |None:
+        """
+        The AEAD mode the message was encrypted with, or `None` for SEIPDv1.
+        """
+    @property
     def bytes(self, /) -> bytes |None:
         """
         The decrypted or verified content bytes, or `None` for file-based operations.
         """
     @property
+    def cipher(self, /) -> SymmetricAlgorithm |None:
+        """
+        The symmetric cipher the message was encrypted with.
+        """
+    @property
+    def compression(self, /) -> CompressionAlgorithm |None:
+        """
+        The compression algorithm of the message, if it was compressed.
+        """
+    @property
+    def decryption_key(self, /) -> str |None:
+        """
+        The fingerprint of the (sub)key that decrypted the message, if any.
+        """
+    @property
+    def decryption_method(self, /) -> str |None:
+        """
+        How the message was decrypted: `"key"`, `"password"` or `"session_key"`.
+
+        `None` if the message was not encrypted.
+        """
+    @property
+    def filename(self, /) -> str |None:
+        """
+        The file name stored with the content, or `None` if the sender did not set one.
+        """
+    @property
+    def format(self, /) -> DataFormat |None:
+        """
+        The format of the content, or `None` for detached signatures.
+        """
+    @property
+    def layers(self, /) -> list[MessageLayer]:
+        """
+        The layers of the message from the outermost to the innermost one.
+        """
+    @property
+    def literal_date(self, /) -> datetime |None:
+        """
+        The date stored with the content, or `None` if the sender did not set one.
+        """
+    @property
+    def seipd_version(self, /) -> int |None:
+        """
+        The version of the encrypted data packet: 1, or 2 for AEAD.
+        """
+    @property
+    def session_key(self, /) -> str |None:
+        """
+        The session key of the message in the `ALGO:HEX` form, if it was requested.
+
+        It decrypts this message only and can be passed as `session_keys` to `decrypt`.
+        """
+    @property
     def valid_sigs(self, /) -> list[Any]:
         """
         The list of valid signatures found during verification.
+
+        Signatures outside of the encryption do not sign the plaintext and are
+        only reported in `layers`.
         """
 
 @final
@@ -250,6 +356,330 @@ class EncryptionAlgorithm:
     def __ne__(self, value: object, /) -> bool: ...
     def __repr__(self, /) -> str: ...
 
+@final
+class EncryptionReport:
+    """
+    A description of how a message was encrypted.
+
+    Returned by `encrypt` and `encrypt_file` when called with `report=True`.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def aead(self, /) -> AEADAlgorithm |None:
+        """
+        The AEAD mode used to encrypt the data, or `None` for SEIPDv1.
+        """
+    @property
+    def cipher(self, /) -> SymmetricAlgorithm:
+        """
+        The symmetric cipher used to encrypt the data.
+        """
+    @property
+    def recipients(self, /) -> list[RecipientReport]:
+        """
+        The recipient certificates and the keys used for each of them.
+        """
+    @property
+    def seipd_version(self, /) -> int:
+        """
+        The version of the encrypted data packet: 1, or 2 for AEAD.
+        """
+
+@final
+class Encryptor:
+    """
+    An encrypting writer for data produced incrementally.
+
+    Encrypts everything passed to `write` and writes the resulting message
+    to `sink`, which can be any binary file-like object. The message is
+    completed by `close`, or on leaving the `with` block without an
+    exception. Takes the same parameters as `encrypt`.
+    """
+    def __enter__(self, /) -> Encryptor: ...
+    def __exit__(self, /, exc_type: Any |None, _exc_value: Any |None, _traceback: Any |None) -> bool:
+        """
+        Complete the message, unless the block raised an exception.
+
+        On exceptions the message is left unfinished so that truncated
+        plaintext is never presented as a complete message.
+        """
+    def __new__(cls, /, sink: Any, recipients: Sequence[Cert |Recipient |str] = ..., signer: PySigner |None = None, passwords: Sequence[str] = ..., *, armor: bool = True, keyring: Keyring |None = None, hidden_recipients: Sequence[Cert |Recipient |str] = ..., cipher: SymmetricAlgorithm |None = None, aead: AEADAlgorithm |None = None, compression: CompressionAlgorithm |None = None, filename: str |None = None, date: datetime |None = None, format: DataFormat |None = None, signers: Sequence[PySigner] = ..., padding: Padding |None = None) -> Encryptor:
+        """
+        Start writing an encrypted message to `sink`.
+        """
+    def close(self, /) -> None:
+        """
+        Finish the message and flush it to the sink.
+
+        The sink itself is not closed. Calling `close` more than once has no effect.
+        """
+    @property
+    def closed(self, /) -> bool:
+        """
+        Whether the message has been completed.
+        """
+    def write(self, /, data: bytes) -> int:
+        """
+        Encrypt a chunk of data, returning the number of bytes consumed.
+        """
+
+@final
+class KeyInfo:
+    """
+    Describes a locked secret key to a password callback.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def attempt(self, /) -> int:
+        """
+        The number of the current attempt, starting at 1; higher numbers
+        mean that the previous password was wrong.
+        """
+    @property
+    def certificate(self, /) -> str:
+        """
+        The fingerprint of the certificate the key belongs to.
+        """
+    @property
+    def fingerprint(self, /) -> str:
+        """
+        The fingerprint of the locked (sub)key.
+        """
+    @property
+    def user_id(self, /) -> str |None:
+        """
+        The primary User ID of the certificate, if it has one.
+        """
+
+@final
+class Keyring:
+    """
+    A collection of certificates with named recipient groups.
+
+    Groups map a name (e.g. `"ops@example.org"`) to the fingerprints of
+    their members. Group names and email addresses can be passed as
+    `recipients` to `encrypt` and `encrypt_file` together with `keyring=`.
+    """
+    def __len__(self, /) -> int: ...
+    def __new__(cls, /, certs: Sequence[Cert] = ...) -> Keyring:
+        """
+        Create a keyring holding the given certificates.
+        """
+    def __repr__(self, /) -> str: ...
+    def add(self, /, cert: Cert) -> None:
+        """
+        Add a certificate, merging it with an existing copy if present.
+        """
+    def add_group(self, /, name: str, fingerprints: Sequence[str]) -> None:
+        """
+        Define a recipient group, replacing any existing group with the same name.
+
+        All `fingerprints` must belong to certificates in the keyring.
+        """
+    @property
+    def certs(self, /) -> list[Cert]:
+        """
+        The certificates in this keyring.
+        """
+    @property
+    def groups(self, /) -> dict[str, list[str]]:
+        """
+        The recipient groups, mapping group names to member fingerprints.
+        """
+    def remove_group(self, /, name: str) -> None:
+        """
+        Remove a recipient group.
+        """
+    def resolve(self, /, name: str) -> list[Cert]:
+        """
+        Resolve a group name or email address to the matching certificates.
+        """
+
+@final
+class KeyServer:
+    """
+    A keyserver client supporting the HKP and VKS protocols.
+
+    The `url` is the base URL of the keyserver, e.g. `https://keys.openpgp.org`.
+    The optional `transport` callable is called with the URL (and, for uploads,
+    the request body and its content type) and must return the response body
+    as bytes; by default `urllib` is used.
+    """
+    def __new__(cls, /, url: str = "https://keys.openpgp.org", protocol: KeyServerProtocol = ..., *, transport: Any |None = None) -> KeyServer:
+        """
+        Create a client for the keyserver at `url`.
+        """
+    def __repr__(self, /) -> str: ...
+    def get(self, /, query: str) -> list[Cert]:
+        """
+        Retrieve certificates by fingerprint, key ID or email address.
+        """
+    def send(self, /, cert: Cert) -> str:
+        """
+        Upload a certificate to the keyserver.
+
+        Only the public, exportable parts of the certificate are sent.
+        Returns the response body sent back by the server.
+        """
+    @property
+    def url(self, /) -> str:
+        """
+        The base URL of the keyserver.
+        """
+
+@final
+class KeyServerProtocol:
+    """
+    The protocol spoken by a keyserver.
+    """
+    HKP: Final[KeyServerProtocol]
+    """
+    HTTP Keyserver Protocol (`/pks/lookup` and `/pks/add`)
+    """
+    VKS: Final[KeyServerProtocol]
+    """
+    Verifying Keyserver API used by keys.openpgp.org (`/vks/v1/...`)
+    """
+    def __eq__(self, value: object, /) -> bool: ...
+    def __int__(self, /) -> int: ...
+    def __ne__(self, value: object, /) -> bool: ...
+    def __repr__(self, /) -> str: ...
+
+@final
+class MessageInfo:
+    """
+    A description of an OpenPGP message obtained without decrypting it.
+
+    Packets inside compressed or encrypted data are not visible.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def aead(self, /) -> AEADAlgorithm |None:
+        """
+        The AEAD mode of SEIPDv2 data.
+        """
+    @property
+    def armor(self, /) -> ArmorKind |None:
+        """
+        The kind of ASCII armor, or `None` for binary messages.
+        """
+    @property
+    def cipher(self, /) -> SymmetricAlgorithm |None:
+        """
+        The symmetric cipher of SEIPDv2 data; for SEIPDv1 it is only
+        known after decrypting a session key.
+        """
+    @property
+    def compressed(self, /) -> bool:
+        """
+        Whether the message is compressed outside of any encryption.
+        """
+    @property
+    def compression(self, /) -> CompressionAlgorithm |None:
+        """
+        The compression algorithm, if the message is compressed with a known one.
+        """
+    @property
+    def encrypted(self, /) -> bool:
+        """
+        Whether the message is encrypted.
+        """
+    @property
+    def recipients(self, /) -> list[PkeskInfo]:
+        """
+        The PKESK packets, one for each recipient key.
+        """
+    @property
+    def seipd_version(self, /) -> int |None:
+        """
+        The version of the encrypted data packet: 1, or 2 for AEAD.
+        """
+    @property
+    def signatures(self, /) -> int:
+        """
+        The number of signatures outside of any compression or encryption.
+        """
+    @property
+    def signed(self, /) -> bool:
+        """
+        Whether the message carries signatures outside of any compression or encryption.
+        """
+    @property
+    def skesks(self, /) -> list[SkeskInfo]:
+        """
+        The SKESK packets, one for each password.
+        """
+
+@final
+class MessageLayer:
+    """
+    A layer of a decrypted or verified message.
+
+    Layers are listed from the outermost to the innermost one.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def aead(self, /) -> AEADAlgorithm |None:
+        """
+        The AEAD mode of an encryption layer, or `None` for SEIPDv1.
+        """
+    @property
+    def cipher(self, /) -> SymmetricAlgorithm |None:
+        """
+        The symmetric cipher of an encryption layer.
+        """
+    @property
+    def compression(self, /) -> CompressionAlgorithm |None:
+        """
+        The compression algorithm of a compression layer.
+        """
+    @property
+    def errors(self, /) -> list[str]:
+        """
+        Why the other signatures of a signature group could not be verified.
+        """
+    @property
+    def kind(self, /) -> str:
+        """
+        The kind of the layer: `"encryption"`, `"compression"` or `"signature_group"`.
+        """
+    @property
+    def valid_sigs(self, /) -> list[Any]:
+        """
+        The valid signatures of a signature group.
+        """
+
+@final
+class MessageReader:
+    """
+    A readable stream of plaintext from a decrypted or verified message.
+
+    Returned by `open_decrypt` and `open_verify`. Supports `read`, iteration
+    over chunks and use as a context manager. Signatures are checked when the
+    end of the message is reached: a failed check is raised by the final read
+    and again by every later read, and `valid_sigs` is only available once the
+    whole message has been read successfully.
+    """
+    def __enter__(self, /) -> MessageReader: ...
+    def __exit__(self, /, _exc_type: Any |None, _exc_value: Any |None, _traceback: Any |None) -> bool: ...
+    def __iter__(self, /) -> MessageReader: ...
+    def __next__(self, /) -> bytes |None: ...
+    def close(self, /) -> None:
+        """
+        Stop reading and release the underlying source.
+        """
+    def read(self, /, size: int = -1) -> bytes:
+        """
+        Read up to `size` bytes of plaintext, or everything that is left if `size` is negative.
+
+        Returns an empty `bytes` object at the end of the message.
+        """
+    @property
+    def valid_sigs(self, /) -> list[Any]:
+        """
+        The list of valid signatures, available once the whole message has been read.
+        """
+
 @final
 class Notation:
     """
@@ -272,6 +702,60 @@ class Notation:
         The notation value.
         """
 
+@final
+class Padding:
+    """
+    The padding applied to encrypted data to hide the length of the plaintext.
+    """
+    BUCKETS: Final[Padding]
+    """
+    Pad to the next power of two, and to at least 512 bytes
+    """
+    NONE: Final[Padding]
+    """
+    No padding
+    """
+    PADME: Final[Padding]
+    """
+    Padmé, which adds at most 12% and leaks O(log log n) bits of the length
+    """
+    def __eq__(self, value: object, /) -> bool: ...
+    def __int__(self, /) -> int: ...
+    def __ne__(self, value: object, /) -> bool: ...
+    def __repr__(self, /) -> str: ...
+
+@final
+class PkeskInfo:
+    """
+    Describes a public-key encrypted session key (PKESK) packet.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def algorithm(self, /) -> PublicKeyAlgorithm |None:
+        """
+        The public key algorithm of the recipient key, if known.
+        """
+    @property
+    def fingerprint(self, /) -> str |None:
+        """
+        The fingerprint of the recipient key, which only version 6 PKESKs carry.
+        """
+    @property
+    def key_id(self, /) -> str |None:
+        """
+        The key ID of the recipient key, or `None` for a wildcard recipient.
+        """
+    @property
+    def version(self, /) -> int:
+        """
+        The version of the PKESK packet: 3, or 6 for SEIPDv2 messages.
+        """
+    @property
+    def wildcard(self, /) -> bool:
+        """
+        Whether the recipient is hidden behind a wildcard key ID.
+        """
+
 @final
 class Profile:
     """
@@ -302,6 +786,96 @@ class PySigner:
     Obtain a `PySigner` via `Tsk.signer()` or `Tsk.certifier()`.
     """
 
+@final
+class Recipient:
+    """
+    A recipient certificate with control over which of its keys are used.
+
+    `purpose` restricts the keys to those marked for `"storage"` or
+    `"transport"` encryption, `"any"` (the default) accepts both. `subkeys`
+    selects keys by fingerprint. Unlike a plain `Cert`, whose expired keys
+    are used if it has no alive ones, expired keys are only used with
+    `allow_expired=True`.
+    """
+    def __new__(cls, /, cert: Cert, *, subkeys: Sequence[str] = ..., purpose: str = ..., allow_expired: bool = False) -> Recipient:
+        """
+        Select the keys of `cert` to encrypt to.
+        """
+    def __repr__(self, /) -> str: ...
+    @property
+    def allow_expired(self, /) -> bool:
+        """
+        Whether expired keys may be used.
+        """
+    @property
+    def cert(self, /) -> Cert:
+        """
+        The recipient certificate.
+        """
+    @property
+    def purpose(self, /) -> str:
+        """
+        The purpose the keys must be marked for: `"storage"`, `"transport"` or `"any"`.
+        """
+    @property
+    def subkeys(self, /) -> list[str]:
+        """
+        The fingerprints of the keys to encrypt to, or an empty list for all suitable keys.
+        """
+
+@final
+class RecipientReport:
+    """
+    The keys a message was encrypted to for one recipient certificate.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def certificate(self, /) -> str:
+        """
+        The fingerprint of the recipient certificate.
+        """
+    @property
+    def expired_fallback(self, /) -> bool:
+        """
+        Whether expired keys were used because the certificate has no alive encryption key.
+        """
+    @property
+    def hidden(self, /) -> bool:
+        """
+        Whether the keys are hidden behind wildcard key IDs.
+        """
+    @property
+    def subkeys(self, /) -> list[str]:
+        """
+        The fingerprints of the (sub)keys the message was encrypted to.
+        """
+
+@final
+class S2K:
+    """
+    The string-to-key method deriving a symmetric key from a password.
+    """
+    Argon2: Final[S2K]
+    """
+    Argon2 memory-hard key derivation (RFC 9580)
+    """
+    Iterated: Final[S2K]
+    """
+    Iterated and salted hashing
+    """
+    Salted: Final[S2K]
+    """
+    Salted hashing, deprecated
+    """
+    Simple: Final[S2K]
+    """
+    Plain hashing, deprecated
+    """
+    def __eq__(self, value: object, /) -> bool: ...
+    def __int__(self, /) -> int: ...
+    def __ne__(self, value: object, /) -> bool: ...
+    def __repr__(self, /) -> str: ...
+
 @final
 class Sig:
     """
@@ -420,6 +994,37 @@ class SignatureMode:
     def __ne__(self, value: object, /) -> bool: ...
     def __repr__(self, /) -> str: ...
 
+@final
+class SignaturePolicy:
+    """
+    Requirements a message's signatures have to meet.
+
+    Valid signatures by at least `min_valid` distinct certificates are
+    required. If `signers` is given, only signatures by these certificate or
+    signing key fingerprints count; invalid fingerprints raise a `ValueError`.
+    Set `allow_unsigned=True` to also accept messages without any signatures.
+    """
+    def __new__(cls, /, *, signers: Sequence[str] = ..., min_valid: int = 1, allow_unsigned: bool = False) -> SignaturePolicy:
+        """
+        Describe the signatures a message must carry.
+        """
+    def __repr__(self, /) -> str: ...
+    @property
+    def allow_unsigned(self, /) -> bool:
+        """
+        Whether messages without any signatures are accepted.
+        """
+    @property
+    def min_valid(self, /) -> int:
+        """
+        The number of distinct certificates whose valid signatures are required.
+        """
+    @property
+    def signers(self, /) -> list[str]:
+        """
+        The fingerprints of the accepted signers; empty to accept any signer.
+        """
+
 @final
 class SigningAlgorithm:
     """
@@ -462,6 +1067,113 @@ class SigningAlgorithm:
     def __ne__(self, value: object, /) -> bool: ...
     def __repr__(self, /) -> str: ...
 
+@final
+class SkeskInfo:
+    """
+    Describes a password-encrypted session key (SKESK) packet to a password callback.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def aead(self, /) -> AEADAlgorithm |None:
+        """
+        The AEAD mode protecting the session key of a version 6 SKESK.
+        """
+    @property
+    def argon2_memory(self, /) -> int |None:
+        """
+        The memory size of the Argon2 S2K method in KiB.
+        """
+    @property
+    def argon2_parallelism(self, /) -> int |None:
+        """
+        The degree of parallelism of the Argon2 S2K method.
+        """
+    @property
+    def argon2_passes(self, /) -> int |None:
+        """
+        The number of passes of the Argon2 S2K method.
+        """
+    @property
+    def cipher(self, /) -> SymmetricAlgorithm |None:
+        """
+        The symmetric cipher protecting the session key, if known.
+        """
+    @property
+    def s2k(self, /) -> S2K |None:
+        """
+        The string-to-key method deriving the key from the password,
+        or `None` if the method is not known.
+        """
+    @property
+    def s2k_hash(self, /) -> HashAlgorithm |None:
+        """
+        The hash algorithm of the iterated, salted and simple S2K methods.
+        """
+    @property
+    def s2k_hash_bytes(self, /) -> int |None:
+        """
+        The number of bytes hashed by the iterated S2K method.
+        """
+    @property
+    def version(self, /) -> int:
+        """
+        The version of the SKESK packet: 4, or 6 for SEIPDv2 messages.
+        """
+
+@final
+class SymmetricAlgorithm:
+    """
+    The symmetric cipher used to encrypt message data.
+    """
+    AES128: Final[SymmetricAlgorithm]
+    """
+    AES with 128-bit key
+    """
+    AES192: Final[SymmetricAlgorithm]
+    """
+    AES with 192-bit key
+    """
+    AES256: Final[SymmetricAlgorithm]
+    """
+    AES with 256-bit key
+    """
+    Blowfish: Final[SymmetricAlgorithm]
+    """
+    Blowfish (legacy)
+    """
+    CAST5: Final[SymmetricAlgorithm]
+    """
+    CAST5 (legacy)
+    """
+    Camellia128: Final[SymmetricAlgorithm]
+    """
+    Camellia with 128-bit key
+    """
+    Camellia192: Final[SymmetricAlgorithm]
+    """
+    Camellia with 192-bit key
+    """
+    Camellia256: Final[SymmetricAlgorithm]
+    """
+    Camellia with 256-bit key
+    """
+    IDEA: Final[SymmetricAlgorithm]
+    """
+    IDEA (legacy)
+    """
+    TripleDES: Final[SymmetricAlgorithm]
+    """
+    TripleDES (legacy)
+    """
+    Twofish: Final[SymmetricAlgorithm]
+    """
+    Twofish with 256-bit key
+    """
+    def __eq__(self, value: object, /) -> bool: ...
+    def __int__(self, /) -> int: ...
+    def __ne__(self, value: object, /) -> bool: ...
+    def __repr__(self, /) -> str: ...
+
 @final
 class Tsk:
     """
@@ -479,17 +1191,24 @@ class Tsk:
         """
         Return the ASCII-armored secret key representation (Transferable Secret Key).
         """
-    def certifier(self, /, password: str |None = None) -> PySigner:
+    def certifier(self, /, password: str |None = None, *, password_callback: Any |None = None) -> PySigner:
         """
         Get a certifier using this certificate's certification-capable primary key.
 
-        If the secret key is password-protected, provide the password to decrypt it.
+        If the secret key is password-protected, provide the password to decrypt it,
+        or a `password_callback` that is asked for it when the key is first used.
         """
-    def decryptor(self, /, password: str |None = None) -> PyDecryptor:
+    def decryptor(self, /, password: str |None = None, *, exclude_compromised: bool = False, password_callback: Any |None = None) -> PyDecryptor:
         """
-        Get a decryptor using this certificate's encryption component key.
+        Get a decryptor holding all of this certificate's encryption keys.
 
-        If the secret key is password-protected, provide the password to decrypt it.
+        Expired and revoked keys, and keys bound with algorithms the policy no
+        longer accepts, are included so that older messages can still be
+        decrypted; set `exclude_compromised=True` to leave out keys revoked
+        because they were compromised. Each message is decrypted with the key
+        it is addressed to. If the secret keys are password-protected, provide
+        the password to decrypt them, or a `password_callback` that is only
+        asked for the password of a key when it is needed for a message.
         """
     def extract_certificate(self, /) -> Cert:
         """
@@ -525,11 +1244,12 @@ class Tsk:
 
         The generated certificate has a validity period of 3 years.
         """
-    def signer(self, /, password: str |None = None) -> PySigner:
+    def signer(self, /, password: str |None = None, *, password_callback: Any |None = None) -> PySigner:
         """
         Get a signer using this certificate's signing component key.
 
-        If the secret key is password-protected, provide the password to decrypt it.
+        If the secret key is password-protected, provide the password to decrypt it,
+        or a `password_callback` that is asked for it when the key is first used.
         """
 
 @final
@@ -556,59 +1276,151 @@ def armor(data: bytes, kind: ArmorKind) -> str:
     header type, and returns the ASCII-armored string.
     """
 
-def decrypt(bytes: bytes, decryptor: PyDecryptor |None = None, store: Any |None = None, passwords: Sequence[str] = ...) -> Decrypted:
+def decrypt(bytes: bytes, decryptor: PyDecryptor |Tsk |Sequence[PyDecryptor |Tsk] |None = None, store: Any |None = None, passwords: Sequence[str] = ..., *, session_keys: Sequence[str] = ..., export_session_key: bool = False, password_callback: Any |None = None, require_signatures: SignaturePolicy |None = None) -> Decrypted:
     """
     Decrypt an OpenPGP message from bytes.
 
     Provide either a `decryptor` (from a secret key) or `passwords` for password-based decryption.
+    The `decryptor` can also be a `Tsk`, or a list of decryptors and `Tsk`s to try.
+    Messages can also be decrypted with known `session_keys` in the `ALGO:HEX` form.
+    A `password_callback(attempt, skesks)` is asked for a password only when no
+    other key decrypts the message. It receives a `SkeskInfo` for each
+    password-encrypted session key, is asked again if the password is wrong,
+    and returning `None` cancels decryption.
     Optionally provide a `store` callback for signature verification during decryption.
+    Set `export_session_key=True` to make the session key available as `Decrypted.session_key`.
+    With a `store`, at least one valid signature is required unless `require_signatures`
+    sets another `SignaturePolicy`.
     """
 
-def decrypt_file(input: str |PathLike[str], output: str |PathLike[str], decryptor: PyDecryptor |None = None, store: Any |None = None, passwords: Sequence[str] = ...) -> Decrypted:
+def decrypt_file(input: str |PathLike[str], output: str |PathLike[str], decryptor: PyDecryptor |Tsk |Sequence[PyDecryptor |Tsk] |None = None, store: Any |None = None, passwords: Sequence[str] = ..., *, session_keys: Sequence[str] = ..., export_session_key: bool = False, password_callback: Any |None = None, require_signatures: SignaturePolicy |None = None) -> Decrypted:
     """
     Decrypt an OpenPGP message from a file, writing the plaintext to another file.
 
     Provide either a `decryptor` (from a secret key) or `passwords` for password-based decryption.
+    The `decryptor` can also be a `Tsk`, or a list of decryptors and `Tsk`s to try.
+    Messages can also be decrypted with known `session_keys` in the `ALGO:HEX` form.
+    A `password_callback(attempt, skesks)` is asked for a password only when no
+    other key decrypts the message. It receives a `SkeskInfo` for each
+    password-encrypted session key, is asked again if the password is wrong,
+    and returning `None` cancels decryption.
     Optionally provide a `store` callback for signature verification during decryption.
+    Set `export_session_key=True` to make the session key available as `Decrypted.session_key`.
+    With a `store`, at least one valid signature is required unless `require_signatures`
+    sets another `SignaturePolicy`.
     """
 
-def encrypt(bytes: bytes, recipients: Sequence[Cert] = ..., signer: PySigner |None = None, passwords: Sequence[str] = ..., *, armor: bool = True) -> bytes:
+def encrypt(bytes: bytes, recipients: Sequence[Cert |Recipient |str] = ..., signer: PySigner |None = None, passwords: Sequence[str] = ..., *, armor: bool = True, keyring: Keyring |None = None, hidden_recipients: Sequence[Cert |Recipient |str] = ..., cipher: SymmetricAlgorithm |None = None, aead: AEADAlgorithm |None = None, compression: CompressionAlgorithm |None = None, filename: str |None = None, date: datetime |None = None, format: DataFormat |None = None, signers: Sequence[PySigner] = ..., padding: Padding |None = None, report: bool = False) -> bytes |tuple[bytes, EncryptionReport]:
     """
     Encrypt data for the given recipients and/or passwords.
 
-    Optionally sign the message with the given `signer`.
+    Recipients are `Cert` objects, or group names and email addresses
+    resolved through the given `keyring`. Keys of `hidden_recipients` are
+    not identified in the message, their holders have to try all their keys.
+    Optionally sign the message with the given `signer` and any further `signers`.
     Set `armor=False` to produce binary output instead of ASCII-armored.
+    The symmetric `cipher` defaults to AES-256. The `aead` mode requires all
+    recipients to support SEIPDv2; without it, OCB is used if they all do.
+    Set `compression` to compress the data before encrypting it.
+    The `filename`, `date` and `format` are stored with the encrypted data.
+    Set `padding` to hide the length of the data, which requires SEIPDv2.
+    Set `report=True` to also get an `EncryptionReport` of the keys and algorithms used.
     """
 
-def encrypt_file(input: str |PathLike[str], output: str |PathLike[str], recipients: Sequence[Cert] = ..., signer: PySigner |None = None, passwords: Sequence[str] = ..., *, armor: bool = True) -> None:
+def encrypt_file(input: str |PathLike[str], output: str |PathLike[str], recipients: Sequence[Cert |Recipient |str] = ..., signer: PySigner |None = None, passwords: Sequence[str] = ..., *, armor: bool = True, keyring: Keyring |None = None, hidden_recipients: Sequence[Cert |Recipient |str] = ..., cipher: SymmetricAlgorithm |None = None, aead: AEADAlgorithm |None = None, compression: CompressionAlgorithm |None = None, filename: str |None = None, date: datetime |None = None, format: DataFormat |None = None, signers: Sequence[PySigner] = ..., padding: Padding |None = None, report: bool = False) -> EncryptionReport |None:
     """
     Encrypt a file for the given recipients and/or passwords, writing to an output file.
 
-    Optionally sign the message with the given `signer`.
+    Recipients are `Cert` objects, or group names and email addresses
+    resolved through the given `keyring`. Keys of `hidden_recipients` are
+    not identified in the message, their holders have to try all their keys.
+    Optionally sign the message with the given `signer` and any further `signers`.
     Set `armor=False` to produce binary output instead of ASCII-armored.
+    The symmetric `cipher` defaults to AES-256. The `aead` mode requires all
+    recipients to support SEIPDv2; without it, OCB is used if they all do.
+    Set `compression` to compress the data before encrypting it.
+    The `filename`, `date` and `format` are stored with the encrypted data.
+    Set `padding` to hide the length of the data, which requires SEIPDv2.
+    Set `report=True` to also get an `EncryptionReport` of the keys and algorithms used.
+    """
+
+def inspect_message(message: bytes |str |PathLike[str] |Any) -> MessageInfo:
+    """
+    Describe an OpenPGP message without decrypting it.
+
+    The `message` is given as bytes, a path or a binary file-like object,
+    either binary or ASCII-armored. No secret key material is needed.
+    """
+
+def open_decrypt(source: str |PathLike[str] |Any, decryptor: PyDecryptor |Tsk |Sequence[PyDecryptor |Tsk] |None = None, store: Any |None = None, passwords: Sequence[str] = ..., *, session_keys: Sequence[str] = ..., password_callback: Any |None = None) -> MessageReader:
+    """
+    Open an encrypted message for reading its plaintext incrementally.
+
+    The `source` is a path or a binary file-like object. Takes the same
+    parameters as `decrypt`, but returns a `MessageReader` instead of
+    holding the whole plaintext in memory.
+    """
+
+def open_verify(source: str |PathLike[str] |Any, store: Any) -> MessageReader:
+    """
+    Open an inline-signed message for reading its content incrementally.
+
+    The `source` is a path or a binary file-like object. The `store` callback
+    is called with a list of key ID strings and must return a list of `Cert` objects.
+    """
+
+def reencrypt(message: bytes, decryptor: PyDecryptor |Tsk |Sequence[PyDecryptor |Tsk], add_recipients: Sequence[Cert] = ..., remove_recipients: Sequence[Cert] = ..., passwords: Sequence[str] = ..., *, armor: bool = True, s2k: S2K |None = None) -> bytes:
+    """
+    Change the recipients of an encrypted message without re-encrypting its content.
+
+    The `decryptor` is used to recover the session key only. New PKESK packets are
+    created for `add_recipients` and new SKESK packets for `passwords`, while PKESK
+    packets addressed to any key of `remove_recipients` are dropped. The encrypted
+    data packet is copied unchanged, so recipients added to a SEIPDv2 message must
+    support SEIPDv2. The `s2k` method for `passwords` defaults to `S2K.Iterated`;
+    `S2K.Argon2` is only available for SEIPDv2 messages.
+
+    Note that removed recipients can still decrypt copies of the message they
+    have already received, as the session key stays the same.
+    """
+
+def reencrypt_file(input: str |PathLike[str], output: str |PathLike[str], decryptor: PyDecryptor |Tsk |Sequence[PyDecryptor |Tsk], add_recipients: Sequence[Cert] = ..., remove_recipients: Sequence[Cert] = ..., passwords: Sequence[str] = ..., *, armor: bool = True, s2k: S2K |None = None) -> None:
+    """
+    Change the recipients of an encrypted message in a file, writing the result to another file.
+
+    The encrypted data is streamed from `input` to `output`; see `reencrypt` for
+    the other parameters.
     """
 
-def sign(signer: PySigner, bytes: bytes, *, mode: SignatureMode = ..., armor: bool = True) -> bytes:
+def sign(signer: PySigner, bytes: bytes, *, mode: SignatureMode = ..., armor: bool = True, compression: CompressionAlgorithm |None = None, filename: str |None = None, date: datetime |None = None, format: DataFormat |None = None, signers: Sequence[PySigner] = ...) -> bytes:
     """
     Sign data with the given signer.
 
     The `mode` controls whether the signature is inline (the default), detached, or cleartext.
     Set `armor=False` to produce binary output instead of ASCII-armored.
+    Set `compression` to compress inline-signed messages. The `filename`,
+    `date` and `format` are stored with the content of inline-signed messages.
+    Each of the additional `signers` adds another signature alongside the one by `signer`.
     """
 
-def sign_file(signer: PySigner, input: str |PathLike[str], output: str |PathLike[str], *, mode: SignatureMode = ..., armor: bool = True) -> None:
+def sign_file(signer: PySigner, input: str |PathLike[str], output: str |PathLike[str], *, mode: SignatureMode = ..., armor: bool = True, compression: CompressionAlgorithm |None = None, filename: str |None = None, date: datetime |None = None, format: DataFormat |None = None, signers: Sequence[PySigner] = ...) -> None:
     """
     Sign a file with the given signer, writing the result to an output file.
 
     The `mode` controls whether the signature is inline (the default), detached, or cleartext.
     Set `armor=False` to produce binary output instead of ASCII-armored.
+    Set `compression` to compress inline-signed messages. The `filename`,
+    `date` and `format` are stored with the content of inline-signed messages.
+    Each of the additional `signers` adds another signature alongside the one by `signer`.
     """
 
-def verify(bytes: bytes |None = None, store: Any |None = None, file: str |PathLike[str] |None = None, signature: Sig |None = None) -> Decrypted:
+def verify(bytes: bytes |None = None, store: Any |None = None, file: str |PathLike[str] |None = None, signature: Sig |None = None, *, require_signatures: SignaturePolicy |None = None) -> Decrypted:
     """
     Verify an OpenPGP signature.
 
     Provide either `bytes` or `file` as the signed data source. The `store` callback
     is called with a list of key ID strings and must return a list of `Cert` objects.
     For detached signature verification, pass a `Sig` object as `signature`.
+    At least one valid signature is required unless `require_signatures` sets
+    another `SignaturePolicy`.
     """
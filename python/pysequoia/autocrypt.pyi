@@ -0,0 +1,56 @@
+from . import Cert, Tsk
+from typing import final
+
+@final
+class AutocryptHeader:
+    """
+    A parsed `Autocrypt` header.
+    """
+    def __repr__(self, /) -> str: ...
+    @property
+    def addr(self, /) -> str:
+        """
+        The email address the key is used for.
+        """
+    @property
+    def cert(self, /) -> Cert:
+        """
+        The certificate from the `keydata` attribute.
+        """
+    @property
+    def prefer_encrypt(self, /) -> bool:
+        """
+        Whether the sender set `prefer-encrypt=mutual`.
+        """
+
+def generate_header(cert: Cert, addr: str, *, prefer_encrypt: bool = False) -> str:
+    """
+    Generate the value of an `Autocrypt` header for `addr`.
+
+    The certificate is minimized to the primary key, one encryption subkey,
+    one signing subkey and the User IDs matching `addr`. The value is not
+    folded, this is left to the mail library.
+    """
+
+def generate_setup_message(tsk: Tsk, passcode: str |None = None) -> tuple[str, str]:
+    """
+    Generate an Autocrypt Setup Message transferring the secret key.
+
+    The key is encrypted with `passcode`, a new `numeric9x4` passcode is
+    generated if none is given. The `Passphrase-Format` and `Passphrase-Begin`
+    headers are only added for `numeric9x4` passcodes. Returns the armored
+    message and the passcode, which has to be shown to the user.
+    """
+
+def parse_header(value: str) -> AutocryptHeader:
+    """
+    Parse the value of an `Autocrypt` header.
+
+    Non-critical attributes (starting with an underscore) are ignored,
+    unknown critical attributes cause the header to be rejected.
+    """
+
+def parse_setup_message(message: str, passcode: str) -> Tsk:
+    """
+    Decrypt an Autocrypt Setup Message with the `passcode` shown to the user.
+    """
@@ -0,0 +1,27 @@
+from . import Cert
+
+def generate_record(cert: Cert, email: str, *, ttl: int |None = None, generic: bool = False) -> str:
+    """
+    Generate an `OPENPGPKEY` record for `email` in zone file format.
+
+    The certificate is minimized to the primary key, one encryption subkey,
+    one signing subkey and the User IDs matching `email`. Set `generic=True`
+    to use the RFC 3597 generic syntax (`TYPE61 \\# ...`) understood by
+    name servers which do not know the `OPENPGPKEY` type.
+    """
+
+def owner_name(email: str) -> str:
+    """
+    Compute the `OPENPGPKEY` owner name for an email address.
+
+    The local part is hashed with SHA-256 and truncated to 28 octets as
+    described in RFC 7929. The returned name is fully qualified.
+    """
+
+def parse_record(record: str) -> Cert:
+    """
+    Parse an `OPENPGPKEY` record in zone file format.
+
+    Both the `OPENPGPKEY` and the generic `TYPE61` syntax are supported.
+    Record data fetched directly from DNS can be parsed using `Cert.from_bytes`.
+    """
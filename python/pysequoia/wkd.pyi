@@ -0,0 +1,50 @@
+from . import Cert
+from collections.abc import Sequence
+from os import PathLike
+from typing import Any
+
+def advanced_url(email: str) -> str:
+    """
+    The URL at which the certificate for `email` is published using the advanced method.
+    """
+
+def direct_url(email: str) -> str:
+    """
+    The URL at which the certificate for `email` is published using the direct method.
+    """
+
+def fetch(email: str, transport: Any |None = None) -> list[Cert]:
+    """
+    Look up the certificates for `email` in its domain's Web Key Directory.
+
+    The advanced method is tried first, falling back to the direct method if
+    the advanced URL is not found (HTTP 404) or its host does not resolve.
+    The optional `transport` callable is called with the URL and must return
+    the response body as bytes; by default `urllib` is used. Transports signal
+    these lookup failures by raising `urllib.error.HTTPError` or `socket.gaierror`.
+    """
+
+def generate(path: str |PathLike[str], domain: str, certs: Sequence[Cert], *, advanced: bool = True) -> None:
+    """
+    Generate a Web Key Directory for `domain` under the `path` directory.
+
+    Creates the `.well-known/openpgpkey` tree with an empty `policy` file and
+    one `hu/` entry per email address. Only User IDs belonging to `domain`
+    are published, certificates without such User IDs are skipped.
+    Set `advanced=False` to generate the layout for the direct method.
+    """
+
+def local_part_hash(email: str) -> str:
+    """
+    Compute the WKD hash of the local part of an email address.
+
+    The local part is lowercased, hashed with SHA-1 and encoded using z-base-32.
+    """
+
+def parse_response(bytes: bytes, email: str) -> list[Cert]:
+    """
+    Parse a WKD response for `email`.
+
+    Returns only the certificates that carry a User ID with the requested
+    email address, as required by the WKD specification.
+    """
@@ -0,0 +1,202 @@
+use std::io::Write;
+
+use anyhow::{Context, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use pyo3::prelude::*;
+use sequoia_openpgp::armor;
+use sequoia_openpgp::cert;
+use sequoia_openpgp::crypto;
+use sequoia_openpgp::parse::{Parse, stream::DecryptorBuilder};
+use sequoia_openpgp::policy::StandardPolicy;
+use sequoia_openpgp::serialize::stream::{Armorer, Encryptor, LiteralWriter, Message};
+use sequoia_openpgp::serialize::{Serialize, SerializeInto};
+use sequoia_openpgp::types::SymmetricAlgorithm;
+
+use crate::cert::Cert;
+use crate::cert::secret::Tsk;
+use crate::decrypt::PyDecryptor;
+
+/// A parsed `Autocrypt` header.
+#[pyclass]
+pub struct AutocryptHeader {
+    addr: String,
+    prefer_encrypt: bool,
+    cert: Cert,
+}
+
+#[pymethods]
+impl AutocryptHeader {
+    /// The email address the key is used for.
+    #[getter]
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Whether the sender set `prefer-encrypt=mutual`.
+    #[getter]
+    pub fn prefer_encrypt(&self) -> bool {
+        self.prefer_encrypt
+    }
+
+    /// The certificate from the `keydata` attribute.
+    #[getter]
+    pub fn cert(&self) -> Cert {
+        self.cert.clone()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<AutocryptHeader addr={} fingerprint={:x}>",
+            self.addr,
+            self.cert.cert().fingerprint()
+        )
+    }
+}
+
+/// Generate the value of an `Autocrypt` header for `addr`.
+///
+/// The certificate is minimized to the primary key, one encryption subkey,
+/// one signing subkey and the User IDs matching `addr`. The value is not
+/// folded, this is left to the mail library.
+#[pyfunction]
+#[pyo3(signature = (cert, addr, *, prefer_encrypt=false))]
+pub fn generate_header(cert: &Cert, addr: &str, prefer_encrypt: bool) -> PyResult<String> {
+    let keydata = BASE64.encode(cert.minimize(addr)?.export_to_vec()?);
+    if prefer_encrypt {
+        Ok(format!(
+            "addr={addr}; prefer-encrypt=mutual; keydata={keydata}"
+        ))
+    } else {
+        Ok(format!("addr={addr}; keydata={keydata}"))
+    }
+}
+
+/// Parse the value of an `Autocrypt` header.
+///
+/// Non-critical attributes (starting with an underscore) are ignored,
+/// unknown critical attributes cause the header to be rejected.
+#[pyfunction]
+pub fn parse_header(value: &str) -> PyResult<AutocryptHeader> {
+    let mut addr = None;
+    let mut prefer_encrypt = false;
+    let mut keydata = None;
+
+    for attribute in value.split(';').map(str::trim) {
+        if attribute.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = attribute.split_once('=') else {
+            return Err(anyhow!("Malformed Autocrypt attribute: {attribute}").into());
+        };
+        match key.trim() {
+            "addr" => addr = Some(value.trim().to_owned()),
+            "prefer-encrypt" => prefer_encrypt = value.trim() == "mutual",
+            "keydata" => keydata = Some(value.split_whitespace().collect::<String>()),
+            key if key.starts_with('_') => (),
+            key => return Err(anyhow!("Unknown critical Autocrypt attribute: {key}").into()),
+        }
+    }
+
+    let addr = addr.ok_or_else(|| anyhow!("Autocrypt header lacks the `addr` attribute"))?;
+    let keydata =
+        keydata.ok_or_else(|| anyhow!("Autocrypt header lacks the `keydata` attribute"))?;
+    let keydata = BASE64
+        .decode(keydata)
+        .context("Malformed Autocrypt keydata")?;
+
+    Ok(AutocryptHeader {
+        addr,
+        prefer_encrypt,
+        cert: cert::Cert::from_bytes(&keydata)?.into(),
+    })
+}
+
+/// Generates a random `numeric9x4` passcode, e.g. `1234-5678-...`.
+fn generate_passcode() -> anyhow::Result<String> {
+    let mut digits = vec![];
+    while digits.len() < 36 {
+        let mut random = [0u8; 64];
+        crypto::random(&mut random)?;
+        digits.extend(
+            random
+                .iter()
+                .filter(|byte| **byte < 250)
+                .map(|byte| char::from(b'0' + byte % 10)),
+        );
+    }
+    digits.truncate(36);
+    Ok(digits
+        .chunks(4)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-"))
+}
+
+/// Whether `passcode` consists of nine blocks of four digits separated by dashes.
+fn is_numeric9x4(passcode: &str) -> bool {
+    let blocks = passcode.split('-').collect::<Vec<_>>();
+    blocks.len() == 9
+        && blocks
+            .iter()
+            .all(|block| block.len() == 4 && block.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+/// Generate an Autocrypt Setup Message transferring the secret key.
+///
+/// The key is encrypted with `passcode`, a new `numeric9x4` passcode is
+/// generated if none is given. The `Passphrase-Format` and `Passphrase-Begin`
+/// headers are only added for `numeric9x4` passcodes. Returns the armored
+/// message and the passcode, which has to be shown to the user.
+#[pyfunction]
+#[pyo3(signature = (tsk, passcode=None))]
+pub fn generate_setup_message(tsk: &Tsk, passcode: Option<String>) -> PyResult<(String, String)> {
+    let passcode = match passcode {
+        Some(passcode) => passcode,
+        None => generate_passcode()?,
+    };
+
+    let mut key = vec![];
+    let mut writer = armor::Writer::with_headers(
+        &mut key,
+        armor::Kind::SecretKey,
+        [("Autocrypt-Prefer-Encrypt", "mutual")],
+    )?;
+    tsk.cert().as_tsk().serialize(&mut writer)?;
+    writer.finalize()?;
+
+    let mut sink = vec![];
+    let mut armorer = Armorer::new(Message::new(&mut sink)).kind(armor::Kind::Message);
+    if is_numeric9x4(&passcode) {
+        armorer = armorer
+            .add_header("Passphrase-Format", "numeric9x4")
+            .add_header("Passphrase-Begin", &passcode[..2]);
+    }
+    let message = armorer.build()?;
+    let message = Encryptor::with_passwords(message, Some(passcode.clone()))
+        .symmetric_algo(SymmetricAlgorithm::AES128)
+        .build()
+        .context("Failed to create encryptor")?;
+    let mut message = LiteralWriter::new(message)
+        .build()
+        .context("Failed to create literal writer")?;
+    message.write_all(&key)?;
+    message.finalize()?;
+
+    Ok((String::from_utf8(sink)?, passcode))
+}
+
+/// Decrypt an Autocrypt Setup Message with the `passcode` shown to the user.
+#[pyfunction]
+pub fn parse_setup_message(message: &str, passcode: String) -> PyResult<Tsk> {
+    let mut helper = PyDecryptor::default();
+    helper.set_passwords(vec![passcode]);
+
+    let policy = &StandardPolicy::new();
+    let mut decryptor =
+        DecryptorBuilder::from_bytes(message.as_bytes())?.with_policy(policy, None, helper)?;
+
+    let mut key = vec![];
+    std::io::copy(&mut decryptor, &mut key)?;
+    Ok(cert::Cert::from_bytes(&key)?.into())
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::sync::{Arc, Mutex, MutexGuard};
 
+use anyhow::anyhow;
 use once_cell::sync::Lazy;
 use pyo3::prelude::*;
 use sequoia_openpgp::Packet;
@@ -46,6 +47,79 @@ impl Cert {
     pub fn policy(&self) -> MutexGuard<'_, Box<dyn Policy>> {
         self.policy.lock().unwrap()
     }
+
+    /// Returns the email addresses of the valid, unrevoked User IDs.
+    pub(crate) fn emails(&self) -> Vec<String> {
+        let policy = self.policy();
+        let Ok(valid_cert) = self.cert.with_policy(&**policy, None) else {
+            return vec![];
+        };
+        valid_cert
+            .userids()
+            .revoked(false)
+            .filter_map(|ua| ua.userid().email().ok().flatten().map(str::to_owned))
+            .collect()
+    }
+
+    /// Strips the certificate down to the primary key, one encryption subkey,
+    /// one signing subkey and the User IDs matching `addr`.
+    pub(crate) fn minimize(&self, addr: &str) -> anyhow::Result<cert::Cert> {
+        if !self
+            .emails()
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(addr))
+        {
+            return Err(anyhow!("No User ID for {addr} on {}", self.cert));
+        }
+
+        let policy = self.policy();
+        let valid_cert = self.cert.with_policy(&**policy, None)?;
+
+        let encryption = valid_cert
+            .keys()
+            .subkeys()
+            .alive()
+            .revoked(false)
+            .for_transport_encryption()
+            .next()
+            .ok_or_else(|| anyhow!("No suitable encryption subkey for {}", self.cert))?
+            .key()
+            .fingerprint();
+        let signing = valid_cert
+            .keys()
+            .subkeys()
+            .alive()
+            .revoked(false)
+            .for_signing()
+            .next()
+            .map(|key| key.key().fingerprint());
+
+        Ok(self
+            .cert
+            .clone()
+            .retain_userids(|ua| {
+                ua.userid()
+                    .email()
+                    .ok()
+                    .flatten()
+                    .is_some_and(|email| email.eq_ignore_ascii_case(addr))
+            })
+            .retain_user_attributes(|_| false)
+            .retain_subkeys(|ka| {
+                let fingerprint = ka.key().fingerprint();
+                fingerprint == encryption || Some(fingerprint) == signing
+            }))
+    }
+}
+
+/// Splits an email address into its local part and lowercased domain.
+pub(crate) fn split_email(email: &str) -> anyhow::Result<(&str, String)> {
+    match email.rsplit_once('@') {
+        Some((local, domain)) if !local.is_empty() && !domain.is_empty() => {
+            Ok((local, domain.to_lowercase()))
+        }
+        _ => Err(anyhow!("Not a valid email address: {email}")),
+    }
 }
 
 /// The OpenPGP profile to use when generating certificates.
@@ -69,6 +143,7 @@ impl From<Profile> for sequoia_openpgp::Profile {
 }
 
 pub mod secret;
+pub mod unlock;
 
 #[pymethods]
 impl Cert {
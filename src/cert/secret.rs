@@ -2,10 +2,14 @@ use std::borrow::Cow;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use pyo3::prelude::*;
+use sequoia_openpgp::cert::amalgamation::key::PrimaryKey;
+use sequoia_openpgp::cert::amalgamation::{ValidAmalgamation, ValidateAmalgamation};
+use sequoia_openpgp::packet::Signature;
 use sequoia_openpgp::parse::Parse as _;
-use sequoia_openpgp::types::KeyFlags;
+use sequoia_openpgp::types::{KeyFlags, RevocationStatus, RevocationType};
 use sequoia_openpgp::{cert, policy::Policy, serialize::SerializeInto};
 
+use crate::cert::unlock::Unlocked;
 use crate::cert::{DEFAULT_POLICY, Profile};
 use crate::decrypt;
 use crate::signer::PySigner;
@@ -75,11 +79,39 @@ impl Tsk {
             policy: Arc::clone(policy),
         }
     }
+    pub fn cert(&self) -> &cert::Cert {
+        &self.cert
+    }
+
     pub fn policy(&self) -> MutexGuard<'_, Box<dyn Policy>> {
         self.policy.lock().unwrap()
     }
 }
 
+/// Wraps self-revocations in a status regardless of whether the policy accepts them.
+fn revocations<'a>(sigs: impl Iterator<Item = &'a Signature>) -> RevocationStatus<'a> {
+    let sigs = sigs.collect::<Vec<_>>();
+    if sigs.is_empty() {
+        RevocationStatus::NotAsFarAsWeKnow
+    } else {
+        RevocationStatus::Revoked(sigs)
+    }
+}
+
+/// Whether a key was revoked because it was compromised.
+///
+/// Revocations without a reason are treated the same way.
+fn is_compromised(status: RevocationStatus<'_>) -> bool {
+    match status {
+        RevocationStatus::Revoked(revocations) => revocations.iter().any(|revocation| {
+            revocation
+                .reason_for_revocation()
+                .is_none_or(|(reason, _)| reason.revocation_type() == RevocationType::Hard)
+        }),
+        _ => false,
+    }
+}
+
 impl From<cert::Cert> for Tsk {
     fn from(cert: cert::Cert) -> Self {
         Self {
@@ -187,25 +219,33 @@ impl Tsk {
 
     /// Get a signer using this certificate's signing component key.
     ///
-    /// If the secret key is password-protected, provide the password to decrypt it.
-    #[pyo3(signature = (password=None))]
-    pub fn signer(&self, password: Option<String>) -> PyResult<PySigner> {
+    /// If the secret key is password-protected, provide the password to decrypt it,
+    /// or a `password_callback` that is asked for it when the key is first used.
+    #[pyo3(signature = (password=None, *, password_callback=None))]
+    pub fn signer(
+        &self,
+        password: Option<String>,
+        password_callback: Option<Py<PyAny>>,
+    ) -> PyResult<PySigner> {
+        let policy = self.policy();
         if let Some(key) = self
             .cert
             .keys()
             .secret()
-            .with_policy(&**self.policy(), None)
+            .with_policy(&**policy, None)
             .alive()
             .revoked(false)
             .for_signing()
             .next()
         {
-            let mut key = key.key().clone();
-            if let Some(password) = password {
-                key = key.decrypt_secret(&(password[..]).into())?;
-            }
-            let keypair = key.into_keypair()?;
-            Ok(PySigner::new(Box::new(keypair)))
+            let key = Unlocked::new(
+                &self.cert,
+                &**policy,
+                key.key().clone(),
+                password.as_deref(),
+                password_callback.as_ref(),
+            )?;
+            Ok(PySigner::new(key.into_signer()))
         } else {
             Err(anyhow::anyhow!("No suitable signing subkey for {}", self.cert).into())
         }
@@ -213,54 +253,116 @@ impl Tsk {
 
     /// Get a certifier using this certificate's certification-capable primary key.
     ///
-    /// If the secret key is password-protected, provide the password to decrypt it.
-    #[pyo3(signature = (password=None))]
-    pub fn certifier(&self, password: Option<String>) -> PyResult<PySigner> {
+    /// If the secret key is password-protected, provide the password to decrypt it,
+    /// or a `password_callback` that is asked for it when the key is first used.
+    #[pyo3(signature = (password=None, *, password_callback=None))]
+    pub fn certifier(
+        &self,
+        password: Option<String>,
+        password_callback: Option<Py<PyAny>>,
+    ) -> PyResult<PySigner> {
+        let policy = self.policy();
         if let Some(key) = self
             .cert
             .keys()
             .secret()
-            .with_policy(&**self.policy(), None)
+            .with_policy(&**policy, None)
             .alive()
             .revoked(false)
             .for_certification()
             .next()
         {
-            let mut key = key.key().clone();
-            if let Some(password) = password {
-                key = key.decrypt_secret(&(password[..]).into())?;
-            }
-            let keypair = key.into_keypair()?;
-            Ok(PySigner::new(Box::new(keypair)))
+            let key = Unlocked::new(
+                &self.cert,
+                &**policy,
+                key.key().clone(),
+                password.as_deref(),
+                password_callback.as_ref(),
+            )?;
+            Ok(PySigner::new(key.into_signer()))
         } else {
             Err(anyhow::anyhow!("No suitable certifying key for {}", self.cert).into())
         }
     }
 
-    /// Get a decryptor using this certificate's encryption component key.
+    /// Get a decryptor holding all of this certificate's encryption keys.
     ///
-    /// If the secret key is password-protected, provide the password to decrypt it.
-    #[pyo3(signature = (password=None))]
-    pub fn decryptor(&self, password: Option<String>) -> PyResult<decrypt::PyDecryptor> {
-        if let Some(key) = self
-            .cert
-            .keys()
-            .secret()
-            .with_policy(&**self.policy(), None)
-            .alive()
-            .revoked(false)
-            .for_transport_encryption()
-            .for_storage_encryption()
-            .next()
-        {
-            let mut key = key.key().clone();
-            if let Some(password) = password {
-                key = key.decrypt_secret(&(password[..]).into())?;
+    /// Expired and revoked keys, and keys bound with algorithms the policy no
+    /// longer accepts, are included so that older messages can still be
+    /// decrypted; set `exclude_compromised=True` to leave out keys revoked
+    /// because they were compromised. Each message is decrypted with the key
+    /// it is addressed to. If the secret keys are password-protected, provide
+    /// the password to decrypt them, or a `password_callback` that is only
+    /// asked for the password of a key when it is needed for a message.
+    #[pyo3(signature = (password=None, *, exclude_compromised=false, password_callback=None))]
+    pub fn decryptor(
+        &self,
+        password: Option<String>,
+        exclude_compromised: bool,
+        password_callback: Option<Py<PyAny>>,
+    ) -> PyResult<decrypt::PyDecryptor> {
+        let policy = self.policy();
+        let encryption = KeyFlags::empty()
+            .set_transport_encryption()
+            .set_storage_encryption();
+        let cert_revocations = revocations(self.cert.primary_key().self_revocations());
+        let mut keypairs = vec![];
+        let mut error = None;
+        // The keys are not filtered through the policy, so that subkeys bound
+        // with algorithms it no longer accepts can still decrypt old messages.
+        for ka in self.cert.keys().secret() {
+            let binding_signatures = if ka.primary() {
+                self.cert
+                    .primary_key()
+                    .self_signatures()
+                    .chain(self.cert.userids().flat_map(|ua| ua.self_signatures()))
+                    .collect::<Vec<_>>()
+            } else {
+                ka.self_signatures().collect()
+            };
+            let for_encryption = binding_signatures.iter().any(|sig| {
+                sig.key_flags()
+                    .is_some_and(|flags| !(&flags & &encryption).is_empty())
+            });
+            if !for_encryption {
+                continue;
             }
-            let keypair = key.into_keypair()?;
-            Ok(decrypt::PyDecryptor::new(Box::new(keypair)))
-        } else {
-            Err(anyhow::anyhow!("No suitable decryption subkey for {}", self.cert).into())
+            if exclude_compromised
+                && (is_compromised(revocations(ka.self_revocations()))
+                    || is_compromised(cert_revocations.clone()))
+            {
+                continue;
+            }
+            let current = ka.with_policy(&**policy, None).is_ok_and(|ka| {
+                ka.alive().is_ok()
+                    && !matches!(ka.revocation_status(), RevocationStatus::Revoked(_))
+            });
+            let keypair = Unlocked::new(
+                &self.cert,
+                &**policy,
+                ka.key().clone(),
+                password.as_deref(),
+                password_callback.as_ref(),
+            );
+            match keypair {
+                Ok(keypair) => keypairs.push((current, keypair)),
+                Err(err) => error = Some(err),
+            }
+        }
+
+        if keypairs.is_empty() {
+            return Err(error
+                .unwrap_or_else(|| {
+                    anyhow::anyhow!("No suitable decryption subkey for {}", self.cert)
+                })
+                .into());
+        }
+        // Keys that are currently valid are tried first for wildcard recipients.
+        keypairs.sort_by_key(|(current, _)| !current);
+        let mut decryptor = decrypt::PyDecryptor::default();
+        for (_, keypair) in keypairs {
+            decryptor.add_key(keypair.into_decryptor());
         }
+        Ok(decryptor)
     }
 }
@@ -0,0 +1,200 @@
+use anyhow::anyhow;
+use pyo3::prelude::*;
+use sequoia_openpgp::cert::Cert;
+use sequoia_openpgp::crypto::{self, KeyPair, SessionKey, mpi};
+use sequoia_openpgp::packet::Key;
+use sequoia_openpgp::packet::key::{PublicParts, SecretParts, UnspecifiedRole};
+use sequoia_openpgp::policy::Policy;
+use sequoia_openpgp::types::HashAlgorithm;
+
+/// Describes a locked secret key to a password callback.
+#[pyclass(skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct KeyInfo {
+    fingerprint: String,
+    certificate: String,
+    user_id: Option<String>,
+    attempt: u32,
+}
+
+#[pymethods]
+impl KeyInfo {
+    /// The fingerprint of the locked (sub)key.
+    #[getter]
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// The fingerprint of the certificate the key belongs to.
+    #[getter]
+    pub fn certificate(&self) -> &str {
+        &self.certificate
+    }
+
+    /// The primary User ID of the certificate, if it has one.
+    #[getter]
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// The number of the current attempt, starting at 1; higher numbers
+    /// mean that the previous password was wrong.
+    #[getter]
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<KeyInfo fingerprint={} attempt={}>",
+            self.fingerprint, self.attempt
+        )
+    }
+}
+
+/// A password-protected secret key that is unlocked when it is first used.
+///
+/// The callback is called with a `KeyInfo` until it returns the right
+/// password, or `None` to cancel.
+pub(crate) struct LockedKey {
+    key: Key<SecretParts, UnspecifiedRole>,
+    public: Key<PublicParts, UnspecifiedRole>,
+    info: KeyInfo,
+    callback: Py<PyAny>,
+    keypair: Option<KeyPair>,
+    cancelled: bool,
+}
+
+impl LockedKey {
+    fn new(
+        cert: &Cert,
+        policy: &dyn Policy,
+        key: Key<SecretParts, UnspecifiedRole>,
+        callback: Py<PyAny>,
+    ) -> Self {
+        let user_id = cert.with_policy(policy, None).ok().and_then(|cert| {
+            let user_id = cert.primary_userid().ok()?;
+            Some(String::from_utf8_lossy(user_id.userid().value()).into_owned())
+        });
+        let info = KeyInfo {
+            fingerprint: format!("{:x}", key.fingerprint()),
+            certificate: format!("{:x}", cert.fingerprint()),
+            user_id,
+            attempt: 0,
+        };
+        Self {
+            public: key.clone().take_secret().0,
+            key,
+            info,
+            callback,
+            keypair: None,
+            cancelled: false,
+        }
+    }
+
+    fn unlock(&mut self) -> anyhow::Result<&mut KeyPair> {
+        while self.keypair.is_none() {
+            if self.cancelled {
+                return Err(anyhow!(
+                    "Unlocking key {} was cancelled",
+                    self.info.fingerprint
+                ));
+            }
+            self.info.attempt += 1;
+            let password: Option<String> =
+                Python::attach(|py| self.callback.call1(py, (self.info.clone(),))?.extract(py))?;
+            match password {
+                Some(password) => {
+                    if let Ok(key) = self.key.clone().decrypt_secret(&password.into()) {
+                        self.keypair = Some(key.into_keypair()?);
+                    }
+                }
+                None => self.cancelled = true,
+            }
+        }
+        Ok(self.keypair.as_mut().expect("unlocked above"))
+    }
+}
+
+impl crypto::Signer for LockedKey {
+    fn public(&self) -> &Key<PublicParts, UnspecifiedRole> {
+        &self.public
+    }
+
+    fn sign(
+        &mut self,
+        hash_algo: HashAlgorithm,
+        digest: &[u8],
+    ) -> sequoia_openpgp::Result<mpi::Signature> {
+        self.unlock()?.sign(hash_algo, digest)
+    }
+}
+
+impl crypto::Decryptor for LockedKey {
+    fn public(&self) -> &Key<PublicParts, UnspecifiedRole> {
+        &self.public
+    }
+
+    fn decrypt(
+        &mut self,
+        ciphertext: &mpi::Ciphertext,
+        plaintext_len: Option<usize>,
+    ) -> sequoia_openpgp::Result<SessionKey> {
+        self.unlock()?.decrypt(ciphertext, plaintext_len)
+    }
+}
+
+/// A secret key that is either usable right away or unlocked on first use.
+#[expect(
+    clippy::large_enum_variant,
+    reason = "converted into a boxed signer or decryptor right away"
+)]
+pub(crate) enum Unlocked {
+    KeyPair(KeyPair),
+    Locked(LockedKey),
+}
+
+impl Unlocked {
+    /// Prepares `key` for use, decrypting it with `password` if possible.
+    ///
+    /// Keys that stay locked are unlocked later through `password_callback`.
+    pub fn new(
+        cert: &Cert,
+        policy: &dyn Policy,
+        key: Key<SecretParts, UnspecifiedRole>,
+        password: Option<&str>,
+        password_callback: Option<&Py<PyAny>>,
+    ) -> anyhow::Result<Self> {
+        if !key.secret().is_encrypted() {
+            return Ok(Self::KeyPair(key.into_keypair()?));
+        }
+        if let Some(password) = password {
+            match key.clone().decrypt_secret(&password.into()) {
+                Ok(key) => return Ok(Self::KeyPair(key.into_keypair()?)),
+                Err(err) if password_callback.is_none() => return Err(err),
+                Err(_) => (),
+            }
+        }
+        match password_callback {
+            Some(callback) => {
+                let callback = Python::attach(|py| callback.clone_ref(py));
+                Ok(Self::Locked(LockedKey::new(cert, policy, key, callback)))
+            }
+            None => Ok(Self::KeyPair(key.into_keypair()?)),
+        }
+    }
+
+    pub fn into_signer(self) -> Box<dyn crypto::Signer + Send + Sync + 'static> {
+        match self {
+            Self::KeyPair(keypair) => Box::new(keypair),
+            Self::Locked(key) => Box::new(key),
+        }
+    }
+
+    pub fn into_decryptor(self) -> Box<dyn crypto::Decryptor + Send + Sync + 'static> {
+        match self {
+            Self::KeyPair(keypair) => Box::new(keypair),
+            Self::Locked(key) => Box::new(key),
+        }
+    }
+}
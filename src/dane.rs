@@ -0,0 +1,118 @@
+use anyhow::{Context, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use pyo3::prelude::*;
+use sequoia_openpgp::cert;
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::types::HashAlgorithm;
+
+use crate::cert::Cert;
+use crate::cert::split_email;
+
+/// The DNS resource record type number of `OPENPGPKEY`.
+const OPENPGPKEY_TYPE: u16 = 61;
+
+fn hex_decode(hex: &str) -> anyhow::Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(anyhow!("Odd number of hex digits"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid hex digits"))
+        })
+        .collect()
+}
+
+/// Compute the `OPENPGPKEY` owner name for an email address.
+///
+/// The local part is hashed with SHA-256 and truncated to 28 octets as
+/// described in RFC 7929. The returned name is fully qualified.
+#[pyfunction]
+pub fn owner_name(email: &str) -> PyResult<String> {
+    let (local, domain) = split_email(email)?;
+    let digest = crate::digest(HashAlgorithm::SHA256, local.as_bytes())?;
+    let hash = digest[..28]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    Ok(format!(
+        "{hash}._openpgpkey.{}.",
+        domain.trim_end_matches('.')
+    ))
+}
+
+/// Generate an `OPENPGPKEY` record for `email` in zone file format.
+///
+/// The certificate is minimized to the primary key, one encryption subkey,
+/// one signing subkey and the User IDs matching `email`. Set `generic=True`
+/// to use the RFC 3597 generic syntax (`TYPE61 \# ...`) understood by
+/// name servers which do not know the `OPENPGPKEY` type.
+#[pyfunction]
+#[pyo3(signature = (cert, email, *, ttl=None, generic=false))]
+pub fn generate_record(
+    cert: &Cert,
+    email: &str,
+    ttl: Option<u32>,
+    generic: bool,
+) -> PyResult<String> {
+    let owner = owner_name(email)?;
+    let minimized = Cert::from(cert.minimize(email)?);
+    let rdata = minimized.__bytes__()?;
+    let ttl = ttl.map(|ttl| format!(" {ttl}")).unwrap_or_default();
+    if generic {
+        let hex = rdata
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<String>();
+        Ok(format!(
+            "{owner}{ttl} IN TYPE{OPENPGPKEY_TYPE} \\# {} {hex}",
+            rdata.len()
+        ))
+    } else {
+        Ok(format!(
+            "{owner}{ttl} IN OPENPGPKEY {}",
+            BASE64.encode(rdata)
+        ))
+    }
+}
+
+/// Parse an `OPENPGPKEY` record in zone file format.
+///
+/// Both the `OPENPGPKEY` and the generic `TYPE61` syntax are supported.
+/// Record data fetched directly from DNS can be parsed using `Cert.from_bytes`.
+#[pyfunction]
+pub fn parse_record(record: &str) -> PyResult<Cert> {
+    let tokens = record
+        .split_whitespace()
+        .filter(|token| *token != "(" && *token != ")")
+        .collect::<Vec<_>>();
+    let generic_type = format!("TYPE{OPENPGPKEY_TYPE}");
+    let Some(position) = tokens.iter().position(|token| {
+        token.eq_ignore_ascii_case("OPENPGPKEY") || token.eq_ignore_ascii_case(&generic_type)
+    }) else {
+        return Err(anyhow!("Not an OPENPGPKEY record").into());
+    };
+
+    let rdata = if tokens[position].eq_ignore_ascii_case("OPENPGPKEY") {
+        BASE64
+            .decode(tokens[position + 1..].concat())
+            .context("Malformed OPENPGPKEY record data")?
+    } else {
+        let [marker, length, hex @ ..] = &tokens[position + 1..] else {
+            return Err(anyhow!("Malformed generic record data").into());
+        };
+        if *marker != "\\#" {
+            return Err(anyhow!("Malformed generic record data").into());
+        }
+        let rdata = hex_decode(&hex.concat())?;
+        if length.parse::<usize>().ok() != Some(rdata.len()) {
+            return Err(anyhow!("Record data length mismatch").into());
+        }
+        rdata
+    };
+
+    Ok(cert::Cert::from_bytes(&rdata)?.into())
+}
@@ -1,17 +1,34 @@
+#![expect(
+    clippy::too_many_arguments,
+    reason = "decryption options are keyword arguments in Python"
+)]
+
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use anyhow::Context;
+use anyhow::{Context, anyhow};
 use pyo3::prelude::*;
-use sequoia_openpgp::crypto::{Decryptor, Password, SessionKey};
-use sequoia_openpgp::packet::{PKESK, SKESK};
-use sequoia_openpgp::parse::{Parse, stream::*};
+use sequoia_openpgp::crypto::{Decryptor, Password, S2K, SessionKey};
+use sequoia_openpgp::fmt::hex;
+use sequoia_openpgp::packet::{PKESK, Packet, SKESK};
+use sequoia_openpgp::parse::{PacketParser, Parse, stream::*};
 use sequoia_openpgp::policy::StandardPolicy as P;
 use sequoia_openpgp::types::SymmetricAlgorithm;
 use sequoia_openpgp::{KeyHandle, cert};
 
-use crate::verify::PyVerifier;
-use crate::{Decrypted, ValidSig};
+use crate::cert::secret::Tsk;
+use crate::types::{self, HashAlgorithm};
+use crate::verify::{PyVerifier, SignaturePolicy};
+use crate::{Decrypted, LiteralMetadata, PyMessageLayer, ValidSig};
+
+/// What decrypted the session key of a message.
+#[derive(Clone, Debug)]
+pub(crate) enum DecryptedWith {
+    /// The fingerprint of the secret (sub)key.
+    Key(String),
+    Password,
+    SessionKey,
+}
 
 /// A decryption helper that holds the key material needed to decrypt messages.
 ///
@@ -19,20 +36,42 @@ use crate::{Decrypted, ValidSig};
 #[pyclass(from_py_object)]
 #[derive(Clone, Default)]
 pub struct PyDecryptor {
-    inner: Option<Arc<Mutex<Box<dyn Decryptor + Send + Sync + 'static>>>>,
+    keys: Vec<Arc<Mutex<Box<dyn Decryptor + Send + Sync + 'static>>>>,
     verifier: Option<PyVerifier>,
     passwords: Vec<Password>,
+    password_callback: Option<Py<PyAny>>,
+    session_keys: Vec<(Option<SymmetricAlgorithm>, SessionKey)>,
+    session_key: Option<(Option<SymmetricAlgorithm>, SessionKey)>,
+    decrypted_with: Option<DecryptedWith>,
+    literal: LiteralMetadata,
+    layers: Vec<PyMessageLayer>,
 }
 
 impl PyDecryptor {
     pub fn new(inner: Box<dyn Decryptor + Send + Sync + 'static>) -> Self {
         Self {
-            inner: Some(Arc::new(Mutex::new(inner))),
+            keys: vec![Arc::new(Mutex::new(inner))],
             verifier: None,
             passwords: Vec::new(),
+            password_callback: None,
+            session_keys: Vec::new(),
+            session_key: None,
+            decrypted_with: None,
+            literal: LiteralMetadata::default(),
+            layers: Vec::new(),
         }
     }
 
+    /// Adds a secret key to the keys tried by this decryptor.
+    pub fn add_key(&mut self, key: Box<dyn Decryptor + Send + Sync + 'static>) {
+        self.keys.push(Arc::new(Mutex::new(key)));
+    }
+
+    /// Adds the secret keys of `other` to the keys tried by this decryptor.
+    pub fn add_keys(&mut self, other: PyDecryptor) {
+        self.keys.extend(other.keys);
+    }
+
     pub fn set_verifier(&mut self, verifier: impl Into<Option<PyVerifier>>) {
         self.verifier = verifier.into();
     }
@@ -41,6 +80,40 @@ impl PyDecryptor {
         self.passwords = passwords.into_iter().map(Into::into).collect();
     }
 
+    pub fn set_signature_policy(
+        &mut self,
+        signature_policy: SignaturePolicy,
+    ) -> anyhow::Result<()> {
+        match &mut self.verifier {
+            Some(verifier) => {
+                verifier.set_signature_policy(signature_policy);
+                Ok(())
+            }
+            None => Err(anyhow!(
+                "`require_signatures` needs a `store` to look up the signing certificates"
+            )),
+        }
+    }
+
+    pub fn set_password_callback(&mut self, password_callback: Option<Py<PyAny>>) {
+        self.password_callback = password_callback;
+    }
+
+    pub fn set_session_keys(&mut self, session_keys: Vec<String>) -> anyhow::Result<()> {
+        self.session_keys = session_keys
+            .iter()
+            .map(|session_key| parse_session_key(session_key))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(())
+    }
+
+    /// The session key that decrypted the message, in the `ALGO:HEX` form.
+    pub fn session_key(&self) -> Option<String> {
+        self.session_key
+            .as_ref()
+            .map(|(algo, session_key)| format_session_key(*algo, session_key))
+    }
+
     pub fn valid_sigs(self) -> Vec<ValidSig> {
         if let Some(verifier) = self.verifier {
             verifier.valid_sigs()
@@ -48,31 +121,270 @@ impl PyDecryptor {
             vec![]
         }
     }
+
+    /// Describes the decrypted message.
+    fn into_decrypted(self, content: Option<Vec<u8>>, export_session_key: bool) -> Decrypted {
+        Decrypted {
+            content,
+            literal: self.literal.clone(),
+            session_key: export_session_key.then(|| self.session_key()).flatten(),
+            decrypted_with: self.decrypted_with.clone(),
+            layers: self.layers.clone(),
+            valid_sigs: self.valid_sigs(),
+        }
+    }
 }
 
-/// Decrypt an OpenPGP message from bytes.
+/// Parses a session key in the `ALGO:HEX` form used by `sq` and GnuPG.
 ///
-/// Provide either a `decryptor` (from a secret key) or `passwords` for password-based decryption.
-/// Optionally provide a `store` callback for signature verification during decryption.
-#[pyfunction]
-#[pyo3(signature = (bytes, decryptor=None, store=None, passwords=vec![]))]
-pub fn decrypt(
-    bytes: &[u8],
-    decryptor: Option<PyDecryptor>,
+/// The algorithm may be omitted for SEIPDv2 messages, which carry it themselves.
+fn parse_session_key(
+    session_key: &str,
+) -> anyhow::Result<(Option<SymmetricAlgorithm>, SessionKey)> {
+    let (algo, key) = match session_key.split_once(':') {
+        Some((algo, key)) => {
+            let algo: u8 = algo
+                .parse()
+                .with_context(|| format!("Invalid session key algorithm: {algo}"))?;
+            (Some(SymmetricAlgorithm::from(algo)), key)
+        }
+        None => (None, session_key),
+    };
+    let key = hex::decode(key).context("Invalid session key")?;
+    Ok((algo, key.into()))
+}
+
+fn format_session_key(algo: Option<SymmetricAlgorithm>, session_key: &SessionKey) -> String {
+    match algo {
+        Some(algo) => format!("{}:{}", u8::from(algo), hex::encode(&session_key[..])),
+        None => hex::encode(&session_key[..]),
+    }
+}
+
+/// Describes a password-encrypted session key (SKESK) packet to a password callback.
+#[pyclass(skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct SkeskInfo {
+    version: u8,
+    s2k: S2K,
+    cipher: Option<types::SymmetricAlgorithm>,
+    aead: Option<types::AEADAlgorithm>,
+}
+
+impl SkeskInfo {
+    pub fn new(skesk: &SKESK) -> anyhow::Result<Self> {
+        match skesk {
+            SKESK::V4(skesk) => Ok(Self {
+                version: 4,
+                s2k: skesk.s2k().clone(),
+                cipher: skesk.symmetric_algo().try_into().ok(),
+                aead: None,
+            }),
+            SKESK::V6(skesk) => Ok(Self {
+                version: 6,
+                s2k: skesk.s2k().clone(),
+                cipher: skesk.symmetric_algo().try_into().ok(),
+                aead: skesk.aead_algo().try_into().ok(),
+            }),
+            _ => Err(anyhow!("Unsupported SKESK version {}", skesk.version())),
+        }
+    }
+}
+
+#[pymethods]
+impl SkeskInfo {
+    /// The version of the SKESK packet: 4, or 6 for SEIPDv2 messages.
+    #[getter]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The string-to-key method deriving the key from the password,
+    /// or `None` if the method is not known.
+    #[getter]
+    pub fn s2k(&self) -> Option<types::S2K> {
+        (&self.s2k).try_into().ok()
+    }
+
+    /// The hash algorithm of the iterated, salted and simple S2K methods.
+    #[getter]
+    #[expect(deprecated)]
+    pub fn s2k_hash(&self) -> Option<HashAlgorithm> {
+        match self.s2k {
+            S2K::Iterated { hash, .. } | S2K::Salted { hash, .. } | S2K::Simple { hash } => {
+                hash.try_into().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of bytes hashed by the iterated S2K method.
+    #[getter]
+    pub fn s2k_hash_bytes(&self) -> Option<u32> {
+        match self.s2k {
+            S2K::Iterated { hash_bytes, .. } => Some(hash_bytes),
+            _ => None,
+        }
+    }
+
+    /// The number of passes of the Argon2 S2K method.
+    #[getter]
+    pub fn argon2_passes(&self) -> Option<u8> {
+        match self.s2k {
+            S2K::Argon2 { t, .. } => Some(t),
+            _ => None,
+        }
+    }
+
+    /// The degree of parallelism of the Argon2 S2K method.
+    #[getter]
+    pub fn argon2_parallelism(&self) -> Option<u8> {
+        match self.s2k {
+            S2K::Argon2 { p, .. } => Some(p),
+            _ => None,
+        }
+    }
+
+    /// The memory size of the Argon2 S2K method in KiB.
+    #[getter]
+    pub fn argon2_memory(&self) -> Option<u64> {
+        match self.s2k {
+            S2K::Argon2 { m, .. } => 1u64.checked_shl(m.into()),
+            _ => None,
+        }
+    }
+
+    /// The symmetric cipher protecting the session key, if known.
+    #[getter]
+    pub fn cipher(&self) -> Option<types::SymmetricAlgorithm> {
+        self.cipher
+    }
+
+    /// The AEAD mode protecting the session key of a version 6 SKESK.
+    #[getter]
+    pub fn aead(&self) -> Option<types::AEADAlgorithm> {
+        self.aead
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<SkeskInfo version={} s2k={}>",
+            self.version,
+            self.s2k()
+                .map_or_else(|| "None".to_string(), |s2k| format!("{s2k:?}"))
+        )
+    }
+}
+
+/// A source of secret keys: a decryptor or a `Tsk` with unlocked secret keys.
+#[derive(FromPyObject)]
+#[expect(
+    clippy::large_enum_variant,
+    reason = "only lives while the arguments are converted"
+)]
+pub enum DecryptorSource {
+    Decryptor(PyDecryptor),
+    Tsk(Py<Tsk>),
+}
+
+impl DecryptorSource {
+    fn into_decryptor(self) -> PyResult<PyDecryptor> {
+        match self {
+            Self::Decryptor(decryptor) => Ok(decryptor),
+            Self::Tsk(tsk) => Python::attach(|py| tsk.borrow(py).decryptor(None, false, None)),
+        }
+    }
+}
+
+/// The secret keys to decrypt with: a single source or a list of them.
+///
+/// PKESK packets are matched to the right key by their recipient.
+#[derive(FromPyObject)]
+#[expect(
+    clippy::large_enum_variant,
+    reason = "only lives while the arguments are converted"
+)]
+pub enum Decryptors {
+    One(DecryptorSource),
+    Many(Vec<DecryptorSource>),
+}
+
+impl Decryptors {
+    /// Combines all secret keys into a single decryptor.
+    pub(crate) fn into_decryptor(self) -> PyResult<PyDecryptor> {
+        match self {
+            Self::One(source) => source.into_decryptor(),
+            Self::Many(sources) => {
+                let mut decryptor = PyDecryptor::default();
+                for source in sources {
+                    decryptor.add_keys(source.into_decryptor()?);
+                }
+                Ok(decryptor)
+            }
+        }
+    }
+}
+
+/// Combines the decryption parameters into a single helper.
+pub(crate) fn helper(
+    decryptor: Option<Decryptors>,
     store: Option<Py<PyAny>>,
     passwords: Vec<String>,
-) -> PyResult<Decrypted> {
-    if decryptor.is_none() && passwords.is_empty() {
+    session_keys: Vec<String>,
+    password_callback: Option<Py<PyAny>>,
+) -> PyResult<PyDecryptor> {
+    if decryptor.is_none()
+        && passwords.is_empty()
+        && session_keys.is_empty()
+        && password_callback.is_none()
+    {
         return Err(anyhow::anyhow!(
-            "Either `decryptor` or `passwords` parameter should be given and non-empty."
+            "Either `decryptor`, `passwords`, `password_callback` or `session_keys` parameter should be given and non-empty."
         )
         .into());
     }
-    let mut decryptor = decryptor.unwrap_or_default();
+    let mut decryptor = match decryptor {
+        Some(decryptor) => decryptor.into_decryptor()?,
+        None => PyDecryptor::default(),
+    };
     decryptor.set_passwords(passwords);
+    decryptor.set_password_callback(password_callback);
+    decryptor.set_session_keys(session_keys)?;
     if let Some(store) = store {
         decryptor.set_verifier(PyVerifier::from_callback(store));
     }
+    Ok(decryptor)
+}
+
+/// Decrypt an OpenPGP message from bytes.
+///
+/// Provide either a `decryptor` (from a secret key) or `passwords` for password-based decryption.
+/// The `decryptor` can also be a `Tsk`, or a list of decryptors and `Tsk`s to try.
+/// Messages can also be decrypted with known `session_keys` in the `ALGO:HEX` form.
+/// A `password_callback(attempt, skesks)` is asked for a password only when no
+/// other key decrypts the message. It receives a `SkeskInfo` for each
+/// password-encrypted session key, is asked again if the password is wrong,
+/// and returning `None` cancels decryption.
+/// Optionally provide a `store` callback for signature verification during decryption.
+/// Set `export_session_key=True` to make the session key available as `Decrypted.session_key`.
+/// With a `store`, at least one valid signature is required unless `require_signatures`
+/// sets another `SignaturePolicy`.
+#[pyfunction]
+#[pyo3(signature = (bytes, decryptor=None, store=None, passwords=vec![], *, session_keys=vec![], export_session_key=false, password_callback=None, require_signatures=None))]
+pub fn decrypt(
+    bytes: &[u8],
+    decryptor: Option<Decryptors>,
+    store: Option<Py<PyAny>>,
+    passwords: Vec<String>,
+    session_keys: Vec<String>,
+    export_session_key: bool,
+    password_callback: Option<Py<PyAny>>,
+    require_signatures: Option<SignaturePolicy>,
+) -> PyResult<Decrypted> {
+    let mut decryptor = helper(decryptor, store, passwords, session_keys, password_callback)?;
+    if let Some(require_signatures) = require_signatures {
+        decryptor.set_signature_policy(require_signatures)?;
+    }
 
     let policy = &P::new();
 
@@ -81,36 +393,40 @@ pub fn decrypt(
 
     let mut sink = Vec::new();
     std::io::copy(&mut decryptor, &mut sink)?;
-    let decryptor = decryptor.into_helper();
-    Ok(Decrypted {
-        content: Some(sink),
-        valid_sigs: decryptor.valid_sigs(),
-    })
+    Ok(decryptor
+        .into_helper()
+        .into_decrypted(Some(sink), export_session_key))
 }
 
 /// Decrypt an OpenPGP message from a file, writing the plaintext to another file.
 ///
 /// Provide either a `decryptor` (from a secret key) or `passwords` for password-based decryption.
+/// The `decryptor` can also be a `Tsk`, or a list of decryptors and `Tsk`s to try.
+/// Messages can also be decrypted with known `session_keys` in the `ALGO:HEX` form.
+/// A `password_callback(attempt, skesks)` is asked for a password only when no
+/// other key decrypts the message. It receives a `SkeskInfo` for each
+/// password-encrypted session key, is asked again if the password is wrong,
+/// and returning `None` cancels decryption.
 /// Optionally provide a `store` callback for signature verification during decryption.
+/// Set `export_session_key=True` to make the session key available as `Decrypted.session_key`.
+/// With a `store`, at least one valid signature is required unless `require_signatures`
+/// sets another `SignaturePolicy`.
 #[pyfunction]
-#[pyo3(signature = (input, output, decryptor=None, store=None, passwords=vec![]))]
+#[pyo3(signature = (input, output, decryptor=None, store=None, passwords=vec![], *, session_keys=vec![], export_session_key=false, password_callback=None, require_signatures=None))]
 pub fn decrypt_file(
     input: PathBuf,
     output: PathBuf,
-    decryptor: Option<PyDecryptor>,
+    decryptor: Option<Decryptors>,
     store: Option<Py<PyAny>>,
     passwords: Vec<String>,
+    session_keys: Vec<String>,
+    export_session_key: bool,
+    password_callback: Option<Py<PyAny>>,
+    require_signatures: Option<SignaturePolicy>,
 ) -> PyResult<Decrypted> {
-    if decryptor.is_none() && passwords.is_empty() {
-        return Err(anyhow::anyhow!(
-            "Either `decryptor` or `passwords` parameter should be given and non-empty."
-        )
-        .into());
-    }
-    let mut decryptor = decryptor.unwrap_or_default();
-    decryptor.set_passwords(passwords);
-    if let Some(store) = store {
-        decryptor.set_verifier(PyVerifier::from_callback(store));
+    let mut decryptor = helper(decryptor, store, passwords, session_keys, password_callback)?;
+    if let Some(require_signatures) = require_signatures {
+        decryptor.set_signature_policy(require_signatures)?;
     }
     let policy = &P::new();
 
@@ -120,14 +436,19 @@ pub fn decrypt_file(
 
     let mut sink = std::fs::File::create(&output).context("Failed to create output file")?;
     std::io::copy(&mut decryptor, &mut sink)?;
-    let decryptor = decryptor.into_helper();
-    Ok(Decrypted {
-        content: None,
-        valid_sigs: decryptor.valid_sigs(),
-    })
+    Ok(decryptor
+        .into_helper()
+        .into_decrypted(None, export_session_key))
 }
 
 impl VerificationHelper for PyDecryptor {
+    fn inspect(&mut self, pp: &PacketParser) -> sequoia_openpgp::Result<()> {
+        if let Packet::Literal(literal) = &pp.packet {
+            self.literal = LiteralMetadata::from_packet(literal);
+        }
+        Ok(())
+    }
+
     fn get_certs(&mut self, ids: &[KeyHandle]) -> sequoia_openpgp::Result<Vec<cert::Cert>> {
         if let Some(verifier) = &mut self.verifier {
             verifier.get_certs(ids)
@@ -137,46 +458,111 @@ impl VerificationHelper for PyDecryptor {
     }
 
     fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
+        self.layers = structure.into_iter().map(PyMessageLayer::from).collect();
         if let Some(verifier) = &mut self.verifier {
-            verifier.check(structure)
+            verifier.check_layers(self.layers.clone())
         } else {
             Ok(())
         }
     }
 }
 
-impl DecryptionHelper for PyDecryptor {
-    fn decrypt(
+impl PyDecryptor {
+    /// Finds the session key using the known session keys, passwords and secret keys,
+    /// asking the password callback as a last resort.
+    pub(crate) fn find_session_key(
         &mut self,
         pkesks: &[PKESK],
         skesks: &[SKESK],
         sym_algo: Option<SymmetricAlgorithm>,
         decrypt: &mut dyn FnMut(Option<SymmetricAlgorithm>, &SessionKey) -> bool,
-    ) -> sequoia_openpgp::Result<Option<cert::Cert>> {
+    ) -> anyhow::Result<Option<(Option<SymmetricAlgorithm>, SessionKey)>> {
+        for (algo, session_key) in self.session_keys.iter() {
+            if decrypt(algo.or(sym_algo), session_key) {
+                self.decrypted_with = Some(DecryptedWith::SessionKey);
+                return Ok(Some((algo.or(sym_algo), session_key.clone())));
+            }
+        }
+
         for skesk in skesks.iter() {
             for password in self.passwords.iter() {
                 if let Ok((algo, session_key)) = skesk.decrypt(password)
                     && decrypt(algo, &session_key)
                 {
-                    return Ok(None);
+                    self.decrypted_with = Some(DecryptedWith::Password);
+                    return Ok(Some((algo.or(sym_algo), session_key)));
                 }
             }
         }
 
-        if let Some(inner) = &mut self.inner {
-            let pair = &mut *inner.lock().unwrap();
-
+        // PKESKs addressed to one of our keys are tried first, then
+        // wildcard PKESKs (hidden recipients) are tried with every key.
+        for wildcard in [false, true] {
             for pkesk in pkesks.iter() {
-                if pkesk
-                    .decrypt(pair, sym_algo)
-                    .map(|(algo, session_key)| decrypt(algo, &session_key))
-                    .is_some()
-                {
-                    return Ok(None);
+                let recipient = pkesk.recipient();
+                if recipient.is_none() != wildcard {
+                    continue;
+                }
+                for key in self.keys.iter() {
+                    let pair = &mut *key.lock().unwrap();
+                    if let Some(recipient) = &recipient
+                        && !recipient.aliases(pair.public().key_handle())
+                    {
+                        continue;
+                    }
+                    if let Some((algo, session_key)) = pkesk.decrypt(pair, sym_algo)
+                        && decrypt(algo, &session_key)
+                    {
+                        let fingerprint = format!("{:x}", pair.public().fingerprint());
+                        self.decrypted_with = Some(DecryptedWith::Key(fingerprint));
+                        return Ok(Some((algo.or(sym_algo), session_key)));
+                    }
                 }
             }
         }
 
-        Err(anyhow::anyhow!("No key to decrypt message"))
+        if let Some(callback) = &self.password_callback
+            && !skesks.is_empty()
+        {
+            let infos = skesks
+                .iter()
+                .map(SkeskInfo::new)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            for attempt in 1u32.. {
+                let password: Option<String> =
+                    Python::attach(|py| callback.call1(py, (attempt, infos.clone()))?.extract(py))?;
+                let password = Password::from(
+                    password.ok_or_else(|| anyhow!("Password entry was cancelled"))?,
+                );
+                for skesk in skesks.iter() {
+                    if let Ok((algo, session_key)) = skesk.decrypt(&password)
+                        && decrypt(algo, &session_key)
+                    {
+                        self.decrypted_with = Some(DecryptedWith::Password);
+                        return Ok(Some((algo.or(sym_algo), session_key)));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl DecryptionHelper for PyDecryptor {
+    fn decrypt(
+        &mut self,
+        pkesks: &[PKESK],
+        skesks: &[SKESK],
+        sym_algo: Option<SymmetricAlgorithm>,
+        decrypt: &mut dyn FnMut(Option<SymmetricAlgorithm>, &SessionKey) -> bool,
+    ) -> sequoia_openpgp::Result<Option<cert::Cert>> {
+        match self.find_session_key(pkesks, skesks, sym_algo, decrypt)? {
+            Some(session_key) => {
+                self.session_key = Some(session_key);
+                Ok(None)
+            }
+            None => Err(anyhow::anyhow!("No key to decrypt message")),
+        }
     }
 }
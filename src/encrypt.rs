@@ -1,183 +1,963 @@
+#![expect(
+    clippy::too_many_arguments,
+    reason = "encryption options are keyword arguments in Python"
+)]
+
 use std::borrow::Cow;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
-use anyhow::Context;
+use anyhow::{Context, anyhow};
 use pyo3::prelude::*;
+use sequoia_openpgp::Fingerprint;
 use sequoia_openpgp::cert::Preferences;
 use sequoia_openpgp::cert::amalgamation::ValidAmalgamation;
-use sequoia_openpgp::serialize::stream::Recipient;
-use sequoia_openpgp::serialize::stream::{Armorer, Signer};
-use sequoia_openpgp::serialize::stream::{Encryptor, LiteralWriter, Message};
-use sequoia_openpgp::types::KeyFlags;
+use sequoia_openpgp::cert::amalgamation::key::ValidErasedKeyAmalgamation;
+use sequoia_openpgp::crypto::SessionKey;
+use sequoia_openpgp::packet::key::{PublicParts, UnspecifiedRole};
+use sequoia_openpgp::packet::pkesk::{PKESK3, PKESK6};
+use sequoia_openpgp::packet::{PKESK, Packet};
+use sequoia_openpgp::policy::{Policy, StandardPolicy};
+use sequoia_openpgp::serialize::Marshal;
+use sequoia_openpgp::serialize::stream::Armorer;
+use sequoia_openpgp::serialize::stream::padding::{Padder, padme};
+use sequoia_openpgp::serialize::stream::{Encryptor, Message};
+use sequoia_openpgp::types::{
+    AEADAlgorithm as SqAEADAlgorithm, Features, KeyFlags,
+    SymmetricAlgorithm as SqSymmetricAlgorithm,
+};
 
+use crate::LiteralMetadata;
 use crate::cert::Cert;
+use crate::keyring::Keyring;
+use crate::pyio::PyWriter;
 use crate::signer::PySigner;
+use crate::types::{AEADAlgorithm, CompressionAlgorithm, DataFormat, SymmetricAlgorithm};
 
-type RecipientKey = (
-    Option<sequoia_openpgp::types::Features>,
-    sequoia_openpgp::packet::Key<
-        sequoia_openpgp::packet::key::PublicParts,
-        sequoia_openpgp::packet::key::UnspecifiedRole,
-    >,
-);
+/// An encryption-capable key together with the capabilities its certificate advertises.
+pub(crate) struct RecipientKey {
+    cert: Fingerprint,
+    features: Option<Features>,
+    symmetric_algos: Vec<SqSymmetricAlgorithm>,
+    aead_suites: Vec<(SqSymmetricAlgorithm, SqAEADAlgorithm)>,
+    key: sequoia_openpgp::packet::Key<PublicParts, UnspecifiedRole>,
+    /// Whether the PKESK uses a wildcard instead of the key's identifier.
+    hidden: bool,
+    /// Whether the key was only picked because the certificate has no alive one.
+    expired_fallback: bool,
+}
 
-fn resolve_recipient_keys(recipients: &[PyRef<Cert>]) -> PyResult<Vec<RecipientKey>> {
-    let mode = KeyFlags::empty()
-        .set_storage_encryption()
-        .set_transport_encryption();
+impl From<ValidErasedKeyAmalgamation<'_, PublicParts>> for RecipientKey {
+    fn from(ka: ValidErasedKeyAmalgamation<'_, PublicParts>) -> Self {
+        let valid_cert = ka.valid_cert();
+        Self {
+            cert: valid_cert.fingerprint(),
+            features: valid_cert.features(),
+            symmetric_algos: valid_cert
+                .preferred_symmetric_algorithms()
+                .map(<[_]>::to_vec)
+                .unwrap_or_default(),
+            aead_suites: valid_cert
+                .preferred_aead_ciphersuites()
+                .map(<[_]>::to_vec)
+                .unwrap_or_default(),
+            key: ka.key().clone(),
+            hidden: false,
+            expired_fallback: false,
+        }
+    }
+}
 
-    let mut recipient_keys = vec![];
-    for cert in recipients.iter() {
-        let mut found_one = false;
-        let policy = cert.policy();
-
-        for key in cert
-            .cert()
-            .keys()
-            .with_policy(&**policy, None)
-            .supported()
-            .alive()
-            .revoked(false)
-            .key_flags(&mode)
+impl RecipientKey {
+    pub(crate) fn key(&self) -> &sequoia_openpgp::packet::Key<PublicParts, UnspecifiedRole> {
+        &self.key
+    }
+
+    /// Checks that the recipient can decrypt messages using `cipher` and `aead`.
+    ///
+    /// AES-128, and OCB with AES-128, are mandatory to implement, so they are
+    /// accepted even if the certificate does not list them.
+    pub(crate) fn check_algorithms(
+        &self,
+        cipher: Option<SqSymmetricAlgorithm>,
+        aead: Option<SqAEADAlgorithm>,
+    ) -> anyhow::Result<()> {
+        let cert = &self.cert;
+        if let Some(aead) = aead {
+            if !self
+                .features
+                .as_ref()
+                .is_some_and(Features::supports_seipdv2)
+            {
+                return Err(anyhow!(
+                    "Recipient {cert:x} does not support AEAD encryption (SEIPDv2)"
+                ));
+            }
+            let mandatory = (SqSymmetricAlgorithm::AES128, SqAEADAlgorithm::OCB);
+            let supported = self
+                .aead_suites
+                .iter()
+                .chain(std::iter::once(&mandatory))
+                .any(|(suite_cipher, suite_aead)| {
+                    *suite_aead == aead && cipher.is_none_or(|cipher| cipher == *suite_cipher)
+                });
+            if !supported {
+                return Err(match cipher {
+                    Some(cipher) => {
+                        anyhow!("Recipient {cert:x} does not support {cipher} with {aead}")
+                    }
+                    None => anyhow!("Recipient {cert:x} does not support {aead}"),
+                });
+            }
+        } else if let Some(cipher) = cipher
+            && cipher != SqSymmetricAlgorithm::AES128
+            && !self.symmetric_algos.contains(&cipher)
         {
-            recipient_keys.push((key.valid_cert().features(), key.key().clone()));
-            found_one = true;
-        }
-
-        if !found_one {
-            for key in cert
-                .cert()
-                .keys()
-                .with_policy(&**policy, None)
-                .supported()
-                .revoked(false)
-                .key_flags(&mode)
+            return Err(anyhow!("Recipient {cert:x} does not support {cipher}"));
+        }
+        Ok(())
+    }
+
+    /// Creates the PKESK packet encrypting `session_key` to this key.
+    ///
+    /// The `cipher` is only recorded in SEIPDv1 PKESKs.
+    pub(crate) fn pkesk(
+        &self,
+        cipher: SqSymmetricAlgorithm,
+        seipdv2: bool,
+        session_key: &SessionKey,
+    ) -> anyhow::Result<PKESK> {
+        let pkesk = if seipdv2 {
+            let mut pkesk = PKESK6::for_recipient(session_key, &self.key)?;
+            if self.hidden {
+                pkesk.set_recipient(None);
+            }
+            PKESK::V6(pkesk)
+        } else {
+            let mut pkesk = PKESK3::for_recipient(cipher, session_key, &self.key)?;
+            if self.hidden {
+                pkesk.set_recipient(None);
+            }
+            PKESK::V3(pkesk)
+        };
+        Ok(pkesk)
+    }
+}
+
+/// The padding applied to encrypted data to hide the length of the plaintext.
+#[pyclass(eq, from_py_object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+    /// No padding
+    #[pyo3(name = "NONE")]
+    None,
+    /// Pad to the next power of two, and to at least 512 bytes
+    #[pyo3(name = "BUCKETS")]
+    Buckets,
+    /// Padmé, which adds at most 12% and leaks O(log log n) bits of the length
+    #[pyo3(name = "PADME")]
+    Padme,
+}
+
+impl Padding {
+    /// The function mapping the unpadded length to the padded length.
+    fn policy(self) -> Option<fn(u64) -> u64> {
+        match self {
+            Self::None => None,
+            Self::Buckets => Some(buckets),
+            Self::Padme => Some(padme),
+        }
+    }
+}
+
+/// Pads to the next power of two, so that only the bucket of the length is revealed.
+///
+/// The returned size leaves room for the header of the padding packet, so that
+/// the padded data ends exactly at the bucket boundary.
+fn buckets(length: u64) -> u64 {
+    let mut bucket = length.max(512).next_power_of_two();
+    loop {
+        let gap = bucket - length;
+        // A tag octet and a one, two or five octet length precede the padding.
+        for (header, body) in [(2, 0..192), (3, 192..8384), (6, 8384..u64::MAX)] {
+            if gap
+                .checked_sub(header)
+                .is_some_and(|size| body.contains(&size))
             {
-                recipient_keys.push((key.valid_cert().features().clone(), key.key().clone()));
-                found_one = true;
+                return bucket - header;
             }
         }
+        bucket *= 2;
+    }
+}
+
+/// A recipient certificate with control over which of its keys are used.
+///
+/// `purpose` restricts the keys to those marked for `"storage"` or
+/// `"transport"` encryption, `"any"` (the default) accepts both. `subkeys`
+/// selects keys by fingerprint. Unlike a plain `Cert`, whose expired keys
+/// are used if it has no alive ones, expired keys are only used with
+/// `allow_expired=True`.
+#[pyclass(name = "Recipient", from_py_object)]
+#[derive(Clone)]
+pub struct PyRecipient {
+    cert: Cert,
+    subkeys: Vec<Fingerprint>,
+    purpose: String,
+    allow_expired: bool,
+}
+
+#[pymethods]
+impl PyRecipient {
+    /// Select the keys of `cert` to encrypt to.
+    #[new]
+    #[pyo3(signature = (cert, *, subkeys=vec![], purpose="any".to_string(), allow_expired=false))]
+    pub fn new(
+        cert: Cert,
+        subkeys: Vec<String>,
+        purpose: String,
+        allow_expired: bool,
+    ) -> PyResult<Self> {
+        purpose_flags(&purpose)?;
+        let subkeys = subkeys
+            .iter()
+            .map(|subkey| {
+                subkey
+                    .parse::<Fingerprint>()
+                    .with_context(|| format!("Invalid fingerprint: {subkey}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            cert,
+            subkeys,
+            purpose,
+            allow_expired,
+        })
+    }
+
+    /// The recipient certificate.
+    #[getter]
+    pub fn cert(&self) -> Cert {
+        self.cert.clone()
+    }
+
+    /// The fingerprints of the keys to encrypt to, or an empty list for all suitable keys.
+    #[getter]
+    pub fn subkeys(&self) -> Vec<String> {
+        self.subkeys
+            .iter()
+            .map(|subkey| format!("{subkey:x}"))
+            .collect()
+    }
+
+    /// The purpose the keys must be marked for: `"storage"`, `"transport"` or `"any"`.
+    #[getter]
+    pub fn purpose(&self) -> &str {
+        &self.purpose
+    }
+
+    /// Whether expired keys may be used.
+    #[getter]
+    pub fn allow_expired(&self) -> bool {
+        self.allow_expired
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<Recipient cert={:x} purpose={}>",
+            self.cert.cert().fingerprint(),
+            self.purpose
+        )
+    }
+}
+
+fn purpose_flags(purpose: &str) -> anyhow::Result<KeyFlags> {
+    match purpose {
+        "storage" => Ok(KeyFlags::empty().set_storage_encryption()),
+        "transport" => Ok(KeyFlags::empty().set_transport_encryption()),
+        "any" => Ok(KeyFlags::empty()
+            .set_storage_encryption()
+            .set_transport_encryption()),
+        _ => Err(anyhow!(
+            "Unknown key purpose {purpose:?}, expected \"storage\", \"transport\" or \"any\""
+        )),
+    }
+}
+
+/// A recipient given either as a certificate, as a `Recipient` or as a group
+/// name or email address to be resolved through a `Keyring`.
+#[derive(FromPyObject)]
+pub enum RecipientSpec {
+    Cert(Cert),
+    Recipient(PyRecipient),
+    Name(String),
+}
+
+pub(crate) fn cert_recipient_keys(cert: &Cert) -> anyhow::Result<Vec<RecipientKey>> {
+    select_recipient_keys(cert, &purpose_flags("any")?, &[], true)
+}
 
-        if !found_one {
-            return Err(
-                anyhow::anyhow!("No suitable encryption subkey for {}", cert.cert()).into(),
-            );
+/// Selects the valid, unrevoked encryption keys of `cert` marked for `mode`.
+///
+/// Without explicit `subkeys`, all alive keys are used. If there are none,
+/// the expired ones are used instead when `allow_expired` is set.
+fn select_recipient_keys(
+    cert: &Cert,
+    mode: &KeyFlags,
+    subkeys: &[Fingerprint],
+    allow_expired: bool,
+) -> anyhow::Result<Vec<RecipientKey>> {
+    let policy = cert.policy();
+    let (alive, expired): (Vec<_>, Vec<_>) = cert
+        .cert()
+        .keys()
+        .with_policy(&**policy, None)
+        .supported()
+        .revoked(false)
+        .key_flags(mode)
+        .partition(|ka| ka.alive().is_ok());
+
+    if !subkeys.is_empty() {
+        return subkeys
+            .iter()
+            .map(|subkey| {
+                if let Some(ka) = alive.iter().find(|ka| ka.key().fingerprint() == *subkey) {
+                    Ok(ka.clone().into())
+                } else if let Some(ka) = expired.iter().find(|ka| ka.key().fingerprint() == *subkey)
+                {
+                    if !allow_expired {
+                        return Err(anyhow!("Key {subkey:x} of {} is expired", cert.cert()));
+                    }
+                    Ok(RecipientKey {
+                        expired_fallback: true,
+                        ..ka.clone().into()
+                    })
+                } else {
+                    Err(anyhow!(
+                        "Key {subkey:x} is not a suitable encryption key of {}",
+                        cert.cert()
+                    ))
+                }
+            })
+            .collect();
+    }
+
+    if !alive.is_empty() {
+        return Ok(alive.into_iter().map(Into::into).collect());
+    }
+    if expired.is_empty() {
+        return Err(anyhow!("No suitable encryption subkey for {}", cert.cert()));
+    }
+    if !allow_expired {
+        return Err(anyhow!(
+            "All encryption subkeys of {} are expired",
+            cert.cert()
+        ));
+    }
+    Ok(expired
+        .into_iter()
+        .map(|ka| RecipientKey {
+            expired_fallback: true,
+            ..ka.into()
+        })
+        .collect())
+}
+
+fn resolve_recipient_keys(
+    recipients: &[RecipientSpec],
+    keyring: Option<&Keyring>,
+) -> PyResult<Vec<RecipientKey>> {
+    let mut recipient_keys = vec![];
+    for recipient in recipients.iter() {
+        match recipient {
+            RecipientSpec::Cert(cert) => recipient_keys.extend(cert_recipient_keys(cert)?),
+            RecipientSpec::Recipient(recipient) => recipient_keys.extend(select_recipient_keys(
+                &recipient.cert,
+                &purpose_flags(&recipient.purpose)?,
+                &recipient.subkeys,
+                recipient.allow_expired,
+            )?),
+            RecipientSpec::Name(name) => {
+                let Some(keyring) = keyring else {
+                    return Err(anyhow!(
+                        "Recipient {name} can only be resolved with the `keyring` parameter"
+                    )
+                    .into());
+                };
+                let mut unusable = vec![];
+                for cert in keyring.resolve_name(name)? {
+                    match cert_recipient_keys(&cert) {
+                        Ok(keys) => recipient_keys.extend(keys),
+                        Err(_) => unusable.push(format!("{:x}", cert.cert().fingerprint())),
+                    }
+                }
+                if !unusable.is_empty() {
+                    return Err(anyhow!(
+                        "No suitable encryption subkey for {} (resolved from {name})",
+                        unusable.join(", ")
+                    )
+                    .into());
+                }
+            }
         }
     }
     Ok(recipient_keys)
 }
 
+/// The writer stack configuration shared by `encrypt`, `encrypt_file` and `Encryptor`.
+struct Encryption {
+    recipient_keys: Vec<RecipientKey>,
+    passwords: Vec<String>,
+    signers: Vec<PySigner>,
+    armor: bool,
+    cipher: Option<SqSymmetricAlgorithm>,
+    aead: Option<SqAEADAlgorithm>,
+    compression: Option<CompressionAlgorithm>,
+    padding: Option<Padding>,
+    literal: LiteralMetadata,
+}
+
+impl Encryption {
+    fn new(
+        recipients: &[RecipientSpec],
+        hidden_recipients: &[RecipientSpec],
+        keyring: Option<&Keyring>,
+        passwords: Vec<String>,
+    ) -> PyResult<Self> {
+        if recipients.is_empty() && hidden_recipients.is_empty() && passwords.is_empty() {
+            return Err(anyhow!(
+                "Either `recipients` or `passwords` parameter should be given and non-empty."
+            )
+            .into());
+        }
+
+        let mut recipient_keys = resolve_recipient_keys(recipients, keyring)?;
+        for mut recipient_key in resolve_recipient_keys(hidden_recipients, keyring)? {
+            recipient_key.hidden = true;
+            recipient_keys.push(recipient_key);
+        }
+
+        Ok(Self {
+            recipient_keys,
+            passwords,
+            signers: vec![],
+            armor: true,
+            cipher: None,
+            aead: None,
+            compression: None,
+            padding: None,
+            literal: LiteralMetadata::default(),
+        })
+    }
+
+    /// Picks the symmetric cipher and, for SEIPDv2, the AEAD mode.
+    ///
+    /// Like sequoia's `Encryptor`, AES-256 is used unless a `cipher` is given.
+    /// SEIPDv2 is used with the given `aead` mode, or with OCB if every
+    /// recipient supports SEIPDv2. Padded messages always use SEIPDv2, as
+    /// only it can carry padding packets.
+    ///
+    /// If an `aead` mode is given without a `cipher`, the first cipher every
+    /// recipient advertises together with that mode is used instead.
+    fn algorithms(&self) -> (SqSymmetricAlgorithm, Option<SqAEADAlgorithm>) {
+        let seipdv2 = self.padded()
+            || (!self.recipient_keys.is_empty()
+                && self.recipient_keys.iter().all(|recipient_key| {
+                    recipient_key
+                        .features
+                        .as_ref()
+                        .is_some_and(Features::supports_seipdv2)
+                }));
+        let aead = self.aead.or_else(|| seipdv2.then(SqAEADAlgorithm::default));
+        let cipher = match (self.cipher, self.aead) {
+            (Some(cipher), _) => cipher,
+            (None, Some(aead)) => self.suite_cipher(aead).unwrap_or_default(),
+            (None, None) => SqSymmetricAlgorithm::default(),
+        };
+        (cipher, aead)
+    }
+
+    /// Finds a cipher that every recipient supports together with `aead`.
+    ///
+    /// AES-256 is preferred, followed by the ciphers of the recipients' AEAD
+    /// ciphersuites and the mandatory AES-128.
+    fn suite_cipher(&self, aead: SqAEADAlgorithm) -> Option<SqSymmetricAlgorithm> {
+        let policy = StandardPolicy::new();
+        std::iter::once(SqSymmetricAlgorithm::default())
+            .chain(
+                self.recipient_keys
+                    .iter()
+                    .flat_map(|recipient_key| &recipient_key.aead_suites)
+                    .filter(|(_, suite_aead)| *suite_aead == aead)
+                    .map(|(cipher, _)| *cipher),
+            )
+            .chain(std::iter::once(SqSymmetricAlgorithm::AES128))
+            .find(|cipher| {
+                cipher.is_supported()
+                    && policy.symmetric_algorithm(*cipher).is_ok()
+                    && self.recipient_keys.iter().all(|recipient_key| {
+                        recipient_key
+                            .check_algorithms(Some(*cipher), Some(aead))
+                            .is_ok()
+                    })
+            })
+    }
+
+    fn padded(&self) -> bool {
+        self.padding.and_then(Padding::policy).is_some()
+    }
+
+    /// Describes the keys and algorithms used to encrypt the message.
+    fn report(
+        &self,
+        cipher: SqSymmetricAlgorithm,
+        aead: Option<SqAEADAlgorithm>,
+    ) -> anyhow::Result<EncryptionReport> {
+        let mut recipients: Vec<RecipientReport> = vec![];
+        for recipient_key in &self.recipient_keys {
+            let certificate = format!("{:x}", recipient_key.cert);
+            let subkey = format!("{:x}", recipient_key.key.fingerprint());
+            match recipients.iter_mut().find(|recipient| {
+                recipient.certificate == certificate && recipient.hidden == recipient_key.hidden
+            }) {
+                Some(recipient) => {
+                    recipient.subkeys.push(subkey);
+                    recipient.expired_fallback |= recipient_key.expired_fallback;
+                }
+                None => recipients.push(RecipientReport {
+                    certificate,
+                    subkeys: vec![subkey],
+                    expired_fallback: recipient_key.expired_fallback,
+                    hidden: recipient_key.hidden,
+                }),
+            }
+        }
+        Ok(EncryptionReport {
+            recipients,
+            cipher: cipher.try_into()?,
+            aead: aead.map(TryInto::try_into).transpose()?,
+        })
+    }
+
+    /// Builds the writer stack on top of `sink`, returning the literal data writer
+    /// together with a report of the keys and algorithms used.
+    fn build<'a, W>(self, sink: W) -> PyResult<(Message<'a>, EncryptionReport)>
+    where
+        W: Write + Send + Sync + 'a,
+    {
+        if let Some(cipher) = self.cipher
+            && !cipher.is_supported()
+        {
+            return Err(anyhow!("{cipher} is not supported").into());
+        }
+        if let Some(aead) = self.aead
+            && !aead.is_supported()
+        {
+            return Err(anyhow!("{aead} is not supported").into());
+        }
+        let (cipher, aead) = self.algorithms();
+        // An explicit AEAD mode is checked together with the cipher picked for it.
+        let checked_cipher = (self.cipher.is_some() || self.aead.is_some()).then_some(cipher);
+        for recipient_key in &self.recipient_keys {
+            recipient_key.check_algorithms(checked_cipher, self.aead)?;
+        }
+        let policy = StandardPolicy::new();
+        policy
+            .symmetric_algorithm(cipher)
+            .with_context(|| format!("{cipher} is rejected by the policy"))?;
+        if let Some(aead) = aead {
+            policy
+                .aead_algorithm(aead)
+                .with_context(|| format!("{aead} is rejected by the policy"))?;
+        }
+        if self.padded() {
+            for recipient_key in &self.recipient_keys {
+                recipient_key
+                    .check_algorithms(None, aead)
+                    .context("Padding requires SEIPDv2")?;
+            }
+        }
+        let report = self.report(cipher, aead)?;
+
+        let message = Message::new(sink);
+
+        let mut message = if self.armor {
+            Armorer::new(message).build()?
+        } else {
+            message
+        };
+
+        // The PKESKs are written up front so that the writer stack does not
+        // borrow the recipient keys.
+        let session_key = SessionKey::new(cipher.key_size()?)?;
+        for recipient_key in &self.recipient_keys {
+            Packet::PKESK(recipient_key.pkesk(cipher, aead.is_some(), &session_key)?)
+                .serialize(&mut message)?;
+        }
+        let mut encryptor = Encryptor::with_session_key(message, cipher, session_key)?
+            .add_passwords(self.passwords);
+        if let Some(aead) = aead {
+            encryptor = encryptor.aead_algo(aead);
+        }
+        let mut message = encryptor.build().context("Failed to create encryptor")?;
+
+        if let Some(policy) = self.padding.and_then(Padding::policy) {
+            message = Padder::new(message).with_policy(policy).build()?;
+        }
+
+        message = crate::compress(message, self.compression)?;
+
+        if !self.signers.is_empty() {
+            message = crate::signer::signer(message, self.signers)?.build()?;
+        }
+        let message = self
+            .literal
+            .writer(message)
+            .context("Failed to create literal writer")?;
+        Ok((message, report))
+    }
+}
+
+/// The keys a message was encrypted to for one recipient certificate.
+#[pyclass(skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct RecipientReport {
+    certificate: String,
+    subkeys: Vec<String>,
+    expired_fallback: bool,
+    hidden: bool,
+}
+
+#[pymethods]
+impl RecipientReport {
+    /// The fingerprint of the recipient certificate.
+    #[getter]
+    pub fn certificate(&self) -> &str {
+        &self.certificate
+    }
+
+    /// The fingerprints of the (sub)keys the message was encrypted to.
+    #[getter]
+    pub fn subkeys(&self) -> Vec<String> {
+        self.subkeys.clone()
+    }
+
+    /// Whether expired keys were used because the certificate has no alive encryption key.
+    #[getter]
+    pub fn expired_fallback(&self) -> bool {
+        self.expired_fallback
+    }
+
+    /// Whether the keys are hidden behind wildcard key IDs.
+    #[getter]
+    pub fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<RecipientReport certificate={} subkeys={:?}>",
+            self.certificate, self.subkeys
+        )
+    }
+}
+
+/// A description of how a message was encrypted.
+///
+/// Returned by `encrypt` and `encrypt_file` when called with `report=True`.
+#[pyclass(skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct EncryptionReport {
+    recipients: Vec<RecipientReport>,
+    cipher: SymmetricAlgorithm,
+    aead: Option<AEADAlgorithm>,
+}
+
+#[pymethods]
+impl EncryptionReport {
+    /// The recipient certificates and the keys used for each of them.
+    #[getter]
+    pub fn recipients(&self) -> Vec<RecipientReport> {
+        self.recipients.clone()
+    }
+
+    /// The symmetric cipher used to encrypt the data.
+    #[getter]
+    pub fn cipher(&self) -> SymmetricAlgorithm {
+        self.cipher
+    }
+
+    /// The AEAD mode used to encrypt the data, or `None` for SEIPDv1.
+    #[getter]
+    pub fn aead(&self) -> Option<AEADAlgorithm> {
+        self.aead
+    }
+
+    /// The version of the encrypted data packet: 1, or 2 for AEAD.
+    #[getter]
+    pub fn seipd_version(&self) -> u8 {
+        if self.aead.is_some() { 2 } else { 1 }
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<EncryptionReport cipher={:?} aead={} recipients={}>",
+            self.cipher,
+            self.aead
+                .map_or_else(|| "None".to_string(), |aead| format!("{aead:?}")),
+            self.recipients.len()
+        )
+    }
+}
+
+/// The return value of `encrypt`: the message, and the report if requested.
+#[derive(IntoPyObject)]
+pub enum Encrypted {
+    Bytes(Cow<'static, [u8]>),
+    WithReport((Cow<'static, [u8]>, EncryptionReport)),
+}
+
 /// Encrypt data for the given recipients and/or passwords.
 ///
-/// Optionally sign the message with the given `signer`.
+/// Recipients are `Cert` objects, or group names and email addresses
+/// resolved through the given `keyring`. Keys of `hidden_recipients` are
+/// not identified in the message, their holders have to try all their keys.
+/// Optionally sign the message with the given `signer` and any further `signers`.
 /// Set `armor=False` to produce binary output instead of ASCII-armored.
+/// The symmetric `cipher` defaults to AES-256. The `aead` mode requires all
+/// recipients to support SEIPDv2; without it, OCB is used if they all do.
+/// Set `compression` to compress the data before encrypting it.
+/// The `filename`, `date` and `format` are stored with the encrypted data.
+/// Set `padding` to hide the length of the data, which requires SEIPDv2.
+/// Set `report=True` to also get an `EncryptionReport` of the keys and algorithms used.
 #[pyfunction]
-#[pyo3(signature = (bytes, recipients=vec![], signer=None, passwords=vec![], *, armor=true))]
+#[pyo3(signature = (bytes, recipients=vec![], signer=None, passwords=vec![], *, armor=true, keyring=None, hidden_recipients=vec![], cipher=None, aead=None, compression=None, filename=None, date=None, format=None, signers=vec![], padding=None, report=false))]
 pub fn encrypt(
     bytes: &[u8],
-    recipients: Vec<PyRef<Cert>>,
+    recipients: Vec<RecipientSpec>,
     signer: Option<PySigner>,
     passwords: Vec<String>,
     armor: bool,
-) -> PyResult<Cow<'static, [u8]>> {
-    if recipients.is_empty() && passwords.is_empty() {
-        return Err(anyhow::anyhow!(
-            "Either `recipients` or `passwords` parameter should be given and non-empty."
-        )
-        .into());
-    }
-
-    let recipient_keys = resolve_recipient_keys(&recipients)?;
-
-    let mut sink = vec![];
-
-    let message = Message::new(&mut sink);
-
-    let message = if armor {
-        Armorer::new(message).build()?
-    } else {
-        message
+    keyring: Option<PyRef<Keyring>>,
+    hidden_recipients: Vec<RecipientSpec>,
+    cipher: Option<SymmetricAlgorithm>,
+    aead: Option<AEADAlgorithm>,
+    compression: Option<CompressionAlgorithm>,
+    filename: Option<String>,
+    date: Option<chrono::DateTime<chrono::Utc>>,
+    format: Option<DataFormat>,
+    signers: Vec<PySigner>,
+    padding: Option<Padding>,
+    report: bool,
+) -> PyResult<Encrypted> {
+    let encryption = Encryption {
+        signers: signer.into_iter().chain(signers).collect(),
+        armor,
+        cipher: cipher.map(Into::into),
+        aead: aead.map(Into::into),
+        compression,
+        padding,
+        literal: LiteralMetadata {
+            filename,
+            date,
+            format,
+        },
+        ..Encryption::new(
+            &recipients,
+            &hidden_recipients,
+            keyring.as_deref(),
+            passwords,
+        )?
     };
 
-    let encryptor = Encryptor::for_recipients(
-        message,
-        recipient_keys
-            .iter()
-            .map(|(features, key)| Recipient::new(features.clone(), key.key_handle(), key)),
-    )
-    .add_passwords(passwords);
-
-    let mut message = encryptor.build().context("Failed to create encryptor")?;
+    let mut sink = vec![];
 
-    if let Some(signer) = signer {
-        message = Signer::new(message, signer)?.build()?;
-    }
-    let mut message = LiteralWriter::new(message)
-        .build()
-        .context("Failed to create literal writer")?;
+    let (mut message, encryption_report) = encryption.build(&mut sink)?;
 
     message.write_all(bytes.as_ref())?;
 
     message.finalize()?;
 
-    Ok(sink.into())
+    Ok(if report {
+        Encrypted::WithReport((sink.into(), encryption_report))
+    } else {
+        Encrypted::Bytes(sink.into())
+    })
 }
 
 /// Encrypt a file for the given recipients and/or passwords, writing to an output file.
 ///
-/// Optionally sign the message with the given `signer`.
+/// Recipients are `Cert` objects, or group names and email addresses
+/// resolved through the given `keyring`. Keys of `hidden_recipients` are
+/// not identified in the message, their holders have to try all their keys.
+/// Optionally sign the message with the given `signer` and any further `signers`.
 /// Set `armor=False` to produce binary output instead of ASCII-armored.
+/// The symmetric `cipher` defaults to AES-256. The `aead` mode requires all
+/// recipients to support SEIPDv2; without it, OCB is used if they all do.
+/// Set `compression` to compress the data before encrypting it.
+/// The `filename`, `date` and `format` are stored with the encrypted data.
+/// Set `padding` to hide the length of the data, which requires SEIPDv2.
+/// Set `report=True` to also get an `EncryptionReport` of the keys and algorithms used.
 #[pyfunction]
-#[pyo3(signature = (input, output, recipients=vec![], signer=None, passwords=vec![], *, armor=true))]
+#[pyo3(signature = (input, output, recipients=vec![], signer=None, passwords=vec![], *, armor=true, keyring=None, hidden_recipients=vec![], cipher=None, aead=None, compression=None, filename=None, date=None, format=None, signers=vec![], padding=None, report=false))]
 pub fn encrypt_file(
     input: PathBuf,
     output: PathBuf,
-    recipients: Vec<PyRef<Cert>>,
+    recipients: Vec<RecipientSpec>,
     signer: Option<PySigner>,
     passwords: Vec<String>,
     armor: bool,
-) -> PyResult<()> {
-    if recipients.is_empty() && passwords.is_empty() {
-        return Err(anyhow::anyhow!(
-            "Either `recipients` or `passwords` parameter should be given and non-empty."
-        )
-        .into());
-    }
-    let recipient_keys = resolve_recipient_keys(&recipients)?;
+    keyring: Option<PyRef<Keyring>>,
+    hidden_recipients: Vec<RecipientSpec>,
+    cipher: Option<SymmetricAlgorithm>,
+    aead: Option<AEADAlgorithm>,
+    compression: Option<CompressionAlgorithm>,
+    filename: Option<String>,
+    date: Option<chrono::DateTime<chrono::Utc>>,
+    format: Option<DataFormat>,
+    signers: Vec<PySigner>,
+    padding: Option<Padding>,
+    report: bool,
+) -> PyResult<Option<EncryptionReport>> {
+    let encryption = Encryption {
+        signers: signer.into_iter().chain(signers).collect(),
+        armor,
+        cipher: cipher.map(Into::into),
+        aead: aead.map(Into::into),
+        compression,
+        padding,
+        literal: LiteralMetadata {
+            filename,
+            date,
+            format,
+        },
+        ..Encryption::new(
+            &recipients,
+            &hidden_recipients,
+            keyring.as_deref(),
+            passwords,
+        )?
+    };
 
     let mut sink = File::create(&output).context("Failed to create output file")?;
 
-    let message = Message::new(&mut sink);
+    let (mut message, encryption_report) = encryption.build(&mut sink)?;
 
-    let message = if armor {
-        Armorer::new(message).build()?
-    } else {
-        message
-    };
+    let mut input_file = File::open(&input).context("Failed to open input file")?;
+    std::io::copy(&mut input_file, &mut message)?;
 
-    let mut message = Encryptor::for_recipients(
-        message,
-        recipient_keys
-            .iter()
-            .map(|(features, key)| Recipient::new(features.clone(), key.key_handle(), key)),
-    )
-    .add_passwords(passwords)
-    .build()
-    .context("Failed to create encryptor")?;
+    message.finalize()?;
+
+    Ok(report.then_some(encryption_report))
+}
 
-    if let Some(signer) = signer {
-        message = Signer::new(message, signer)?.build()?;
+/// An encrypting writer for data produced incrementally.
+///
+/// Encrypts everything passed to `write` and writes the resulting message
+/// to `sink`, which can be any binary file-like object. The message is
+/// completed by `close`, or on leaving the `with` block without an
+/// exception. Takes the same parameters as `encrypt`.
+#[pyclass(name = "Encryptor")]
+pub struct PyEncryptor {
+    message: Option<Message<'static>>,
+}
+
+impl PyEncryptor {
+    fn message(&mut self) -> PyResult<&mut Message<'static>> {
+        self.message
+            .as_mut()
+            .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("Encryptor is closed"))
     }
-    let mut message = LiteralWriter::new(message)
-        .build()
-        .context("Failed to create literal writer")?;
+}
 
-    let mut input_file = File::open(&input).context("Failed to open input file")?;
-    std::io::copy(&mut input_file, &mut message)?;
+#[pymethods]
+impl PyEncryptor {
+    /// Start writing an encrypted message to `sink`.
+    #[new]
+    #[pyo3(signature = (sink, recipients=vec![], signer=None, passwords=vec![], *, armor=true, keyring=None, hidden_recipients=vec![], cipher=None, aead=None, compression=None, filename=None, date=None, format=None, signers=vec![], padding=None))]
+    pub fn new(
+        sink: Py<PyAny>,
+        recipients: Vec<RecipientSpec>,
+        signer: Option<PySigner>,
+        passwords: Vec<String>,
+        armor: bool,
+        keyring: Option<PyRef<Keyring>>,
+        hidden_recipients: Vec<RecipientSpec>,
+        cipher: Option<SymmetricAlgorithm>,
+        aead: Option<AEADAlgorithm>,
+        compression: Option<CompressionAlgorithm>,
+        filename: Option<String>,
+        date: Option<chrono::DateTime<chrono::Utc>>,
+        format: Option<DataFormat>,
+        signers: Vec<PySigner>,
+        padding: Option<Padding>,
+    ) -> PyResult<Self> {
+        let encryption = Encryption {
+            signers: signer.into_iter().chain(signers).collect(),
+            armor,
+            cipher: cipher.map(Into::into),
+            aead: aead.map(Into::into),
+            compression,
+            padding,
+            literal: LiteralMetadata {
+                filename,
+                date,
+                format,
+            },
+            ..Encryption::new(
+                &recipients,
+                &hidden_recipients,
+                keyring.as_deref(),
+                passwords,
+            )?
+        };
+        Ok(Self {
+            message: Some(encryption.build(PyWriter::new(sink))?.0),
+        })
+    }
 
-    message.finalize()?;
+    /// Encrypt a chunk of data, returning the number of bytes consumed.
+    pub fn write(&mut self, data: &[u8]) -> PyResult<usize> {
+        self.message()?.write_all(data)?;
+        Ok(data.len())
+    }
+
+    /// Finish the message and flush it to the sink.
+    ///
+    /// The sink itself is not closed. Calling `close` more than once has no effect.
+    pub fn close(&mut self) -> PyResult<()> {
+        if let Some(message) = self.message.take() {
+            message.finalize()?;
+        }
+        Ok(())
+    }
 
-    Ok(())
+    /// Whether the message has been completed.
+    #[getter]
+    pub fn closed(&self) -> bool {
+        self.message.is_none()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Complete the message, unless the block raised an exception.
+    ///
+    /// On exceptions the message is left unfinished so that truncated
+    /// plaintext is never presented as a complete message.
+    fn __exit__(
+        &mut self,
+        exc_type: Option<Py<PyAny>>,
+        _exc_value: Option<Py<PyAny>>,
+        _traceback: Option<Py<PyAny>>,
+    ) -> PyResult<bool> {
+        if exc_type.is_none() {
+            self.close()?;
+        } else {
+            self.message = None;
+        }
+        Ok(false)
+    }
 }
@@ -0,0 +1,46 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Percent-encodes everything except unreserved URL characters.
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Performs an HTTP request using the `transport` callable, or `urllib` if none is given.
+///
+/// GET requests call the transport with the URL only, POST requests
+/// additionally pass the request body and its content type.
+pub(crate) fn request(
+    py: Python<'_>,
+    transport: &Option<Py<PyAny>>,
+    url: &str,
+    body: Option<(&[u8], &str)>,
+) -> PyResult<Vec<u8>> {
+    if let Some(transport) = transport {
+        return match body {
+            Some((data, content_type)) => transport.call1(py, (url, data, content_type)),
+            None => transport.call1(py, (url,)),
+        }?
+        .extract(py);
+    }
+
+    let urllib = py.import("urllib.request")?;
+    let response = match body {
+        Some((data, content_type)) => {
+            let headers = PyDict::new(py);
+            headers.set_item("Content-Type", content_type)?;
+            let request = urllib.getattr("Request")?.call1((url, data, headers))?;
+            urllib.call_method1("urlopen", (request,))?
+        }
+        None => urllib.call_method1("urlopen", (url,))?,
+    };
+    response.call_method0("read")?.extract()
+}
@@ -0,0 +1,255 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use pyo3::prelude::*;
+use sequoia_openpgp::armor::{self, ReaderMode};
+use sequoia_openpgp::packet::{PKESK, Packet, SEIP};
+use sequoia_openpgp::parse::buffered_reader::{self, BufferedReader};
+use sequoia_openpgp::parse::{Cookie, Dearmor, PacketParserBuilder, PacketParserResult, Parse};
+use sequoia_openpgp::{KeyHandle, KeyID};
+
+use crate::decrypt::SkeskInfo;
+use crate::pyio::PyReader;
+use crate::types::{
+    AEADAlgorithm, ArmorKind, CompressionAlgorithm, PublicKeyAlgorithm, SymmetricAlgorithm,
+};
+
+/// The message to inspect: bytes, a path or a binary file-like object.
+#[derive(FromPyObject)]
+pub enum MessageSource {
+    Bytes(Vec<u8>),
+    Path(PathBuf),
+    Reader(Py<PyAny>),
+}
+
+impl MessageSource {
+    /// Opens the message for reading, without reading it into memory.
+    fn reader<'a>(self) -> anyhow::Result<Box<dyn BufferedReader<Cookie> + 'a>> {
+        Ok(match self {
+            Self::Bytes(bytes) => {
+                buffered_reader::Generic::with_cookie(Cursor::new(bytes), None, Cookie::default())
+                    .into_boxed()
+            }
+            Self::Path(path) => {
+                buffered_reader::File::with_cookie(path, Cookie::default())?.into_boxed()
+            }
+            Self::Reader(reader) => buffered_reader::Generic::with_cookie(
+                PyReader::new(reader),
+                None,
+                Cookie::default(),
+            )
+            .into_boxed(),
+        })
+    }
+}
+
+/// Describes a public-key encrypted session key (PKESK) packet.
+#[pyclass(skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct PkeskInfo {
+    version: u8,
+    recipient: Option<KeyHandle>,
+    algorithm: Option<PublicKeyAlgorithm>,
+}
+
+impl PkeskInfo {
+    fn new(pkesk: &PKESK) -> Self {
+        Self {
+            version: pkesk.version(),
+            recipient: pkesk.recipient(),
+            algorithm: pkesk.pk_algo().try_into().ok(),
+        }
+    }
+}
+
+#[pymethods]
+impl PkeskInfo {
+    /// The version of the PKESK packet: 3, or 6 for SEIPDv2 messages.
+    #[getter]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The key ID of the recipient key, or `None` for a wildcard recipient.
+    #[getter]
+    pub fn key_id(&self) -> Option<String> {
+        self.recipient
+            .as_ref()
+            .map(|recipient| format!("{:x}", KeyID::from(recipient)))
+    }
+
+    /// The fingerprint of the recipient key, which only version 6 PKESKs carry.
+    #[getter]
+    pub fn fingerprint(&self) -> Option<String> {
+        match &self.recipient {
+            Some(KeyHandle::Fingerprint(fingerprint)) => Some(format!("{fingerprint:x}")),
+            _ => None,
+        }
+    }
+
+    /// The public key algorithm of the recipient key, if known.
+    #[getter]
+    pub fn algorithm(&self) -> Option<PublicKeyAlgorithm> {
+        self.algorithm
+    }
+
+    /// Whether the recipient is hidden behind a wildcard key ID.
+    #[getter]
+    pub fn wildcard(&self) -> bool {
+        self.recipient.is_none()
+    }
+
+    pub fn __repr__(&self) -> String {
+        match self.key_id() {
+            Some(key_id) => format!("<PkeskInfo key_id={key_id}>"),
+            None => "<PkeskInfo wildcard>".to_string(),
+        }
+    }
+}
+
+/// A description of an OpenPGP message obtained without decrypting it.
+///
+/// Packets inside compressed or encrypted data are not visible.
+#[pyclass(skip_from_py_object)]
+#[derive(Clone, Debug, Default)]
+pub struct MessageInfo {
+    armor: Option<ArmorKind>,
+    recipients: Vec<PkeskInfo>,
+    skesks: Vec<SkeskInfo>,
+    seipd_version: Option<u8>,
+    cipher: Option<SymmetricAlgorithm>,
+    aead: Option<AEADAlgorithm>,
+    signatures: usize,
+    compression: Option<CompressionAlgorithm>,
+    compressed: bool,
+}
+
+#[pymethods]
+impl MessageInfo {
+    /// The kind of ASCII armor, or `None` for binary messages.
+    #[getter]
+    pub fn armor(&self) -> Option<ArmorKind> {
+        self.armor.clone()
+    }
+
+    /// The PKESK packets, one for each recipient key.
+    #[getter]
+    pub fn recipients(&self) -> Vec<PkeskInfo> {
+        self.recipients.clone()
+    }
+
+    /// The SKESK packets, one for each password.
+    #[getter]
+    pub fn skesks(&self) -> Vec<SkeskInfo> {
+        self.skesks.clone()
+    }
+
+    /// Whether the message is encrypted.
+    #[getter]
+    pub fn encrypted(&self) -> bool {
+        self.seipd_version.is_some()
+    }
+
+    /// The version of the encrypted data packet: 1, or 2 for AEAD.
+    #[getter]
+    pub fn seipd_version(&self) -> Option<u8> {
+        self.seipd_version
+    }
+
+    /// The symmetric cipher of SEIPDv2 data; for SEIPDv1 it is only
+    /// known after decrypting a session key.
+    #[getter]
+    pub fn cipher(&self) -> Option<SymmetricAlgorithm> {
+        self.cipher
+    }
+
+    /// The AEAD mode of SEIPDv2 data.
+    #[getter]
+    pub fn aead(&self) -> Option<AEADAlgorithm> {
+        self.aead
+    }
+
+    /// Whether the message carries signatures outside of any compression or encryption.
+    #[getter]
+    pub fn signed(&self) -> bool {
+        self.signatures > 0
+    }
+
+    /// The number of signatures outside of any compression or encryption.
+    #[getter]
+    pub fn signatures(&self) -> usize {
+        self.signatures
+    }
+
+    /// Whether the message is compressed outside of any encryption.
+    #[getter]
+    pub fn compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// The compression algorithm, if the message is compressed with a known one.
+    #[getter]
+    pub fn compression(&self) -> Option<CompressionAlgorithm> {
+        self.compression
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<MessageInfo encrypted={} recipients={} skesks={} signatures={}>",
+            self.encrypted(),
+            self.recipients.len(),
+            self.skesks.len(),
+            self.signatures
+        )
+    }
+}
+
+/// Describe an OpenPGP message without decrypting it.
+///
+/// The `message` is given as bytes, a path or a binary file-like object,
+/// either binary or ASCII-armored. No secret key material is needed.
+#[pyfunction]
+pub fn inspect_message(message: MessageSource) -> PyResult<MessageInfo> {
+    let mut reader = message.reader()?;
+    let mut info = MessageInfo::default();
+
+    // Binary messages start with a packet tag, which has the high bit set.
+    if reader.data(1)?.first().is_none_or(|byte| byte & 0x80 == 0) {
+        let mut armored = armor::Reader::from_buffered_reader(reader, ReaderMode::Tolerant(None))?;
+        // Reading the first byte parses the armor header.
+        armored.data(1)?;
+        info.armor = armored.kind().and_then(|kind| kind.try_into().ok());
+        reader = armored.into_boxed();
+    }
+
+    let mut ppr = PacketParserBuilder::from_buffered_reader(reader)?
+        .dearmor(Dearmor::Disabled)
+        .build()?;
+    while let PacketParserResult::Some(pp) = ppr {
+        match &pp.packet {
+            Packet::PKESK(pkesk) => info.recipients.push(PkeskInfo::new(pkesk)),
+            Packet::SKESK(skesk) => info.skesks.push(SkeskInfo::new(skesk)?),
+            Packet::SEIP(seip) => {
+                info.seipd_version = Some(seip.version());
+                if let SEIP::V2(seip) = seip {
+                    info.cipher = seip.symmetric_algo().try_into().ok();
+                    info.aead = seip.aead().try_into().ok();
+                }
+                break;
+            }
+            // One-pass signatures announce the signatures following the literal data.
+            Packet::OnePassSig(_) | Packet::Signature(_) => info.signatures += 1,
+            Packet::CompressedData(compressed) => {
+                info.compressed = true;
+                info.compression = compressed.algo().try_into().ok();
+                break;
+            }
+            Packet::Literal(_) => break,
+            _ => (),
+        }
+        // Containers are not descended into, their headers are all that is needed.
+        ppr = pp.next()?.1;
+    }
+
+    Ok(info)
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use pyo3::prelude::*;
+use sequoia_openpgp::Fingerprint;
+
+use crate::cert::Cert;
+
+/// A collection of certificates with named recipient groups.
+///
+/// Groups map a name (e.g. `"ops@example.org"`) to the fingerprints of
+/// their members. Group names and email addresses can be passed as
+/// `recipients` to `encrypt` and `encrypt_file` together with `keyring=`.
+#[pyclass]
+#[derive(Default)]
+pub struct Keyring {
+    certs: Vec<Cert>,
+    groups: BTreeMap<String, Vec<Fingerprint>>,
+}
+
+impl Keyring {
+    fn find(&self, fingerprint: &Fingerprint) -> Option<&Cert> {
+        self.certs
+            .iter()
+            .find(|cert| &cert.cert().fingerprint() == fingerprint)
+    }
+
+    /// Resolves a group name or email address to the matching certificates.
+    pub fn resolve_name(&self, name: &str) -> anyhow::Result<Vec<Cert>> {
+        if let Some(members) = self.groups.get(name) {
+            return members
+                .iter()
+                .map(|fingerprint| {
+                    self.find(fingerprint).cloned().ok_or_else(|| {
+                        anyhow!("Member {fingerprint:x} of group {name} is not in the keyring")
+                    })
+                })
+                .collect();
+        }
+
+        let certs = self
+            .certs
+            .iter()
+            .filter(|cert| {
+                cert.emails()
+                    .iter()
+                    .any(|email| email.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if certs.is_empty() {
+            Err(anyhow!("No group or certificate for {name} in the keyring"))
+        } else {
+            Ok(certs)
+        }
+    }
+}
+
+#[pymethods]
+impl Keyring {
+    /// Create a keyring holding the given certificates.
+    #[new]
+    #[pyo3(signature = (certs=vec![]))]
+    pub fn new(certs: Vec<Cert>) -> PyResult<Self> {
+        let mut keyring = Self::default();
+        for cert in certs {
+            keyring.add(cert)?;
+        }
+        Ok(keyring)
+    }
+
+    /// Add a certificate, merging it with an existing copy if present.
+    pub fn add(&mut self, cert: Cert) -> PyResult<()> {
+        let fingerprint = cert.cert().fingerprint();
+        if let Some(existing) = self
+            .certs
+            .iter_mut()
+            .find(|existing| existing.cert().fingerprint() == fingerprint)
+        {
+            *existing = existing.merge(&cert)?;
+        } else {
+            self.certs.push(cert);
+        }
+        Ok(())
+    }
+
+    /// The certificates in this keyring.
+    #[getter]
+    pub fn certs(&self) -> Vec<Cert> {
+        self.certs.clone()
+    }
+
+    /// Define a recipient group, replacing any existing group with the same name.
+    ///
+    /// All `fingerprints` must belong to certificates in the keyring.
+    pub fn add_group(&mut self, name: String, fingerprints: Vec<String>) -> PyResult<()> {
+        let mut members = vec![];
+        for fingerprint in fingerprints {
+            let fingerprint: Fingerprint = fingerprint.parse()?;
+            if self.find(&fingerprint).is_none() {
+                return Err(anyhow!("Certificate {fingerprint:x} is not in the keyring").into());
+            }
+            members.push(fingerprint);
+        }
+        self.groups.insert(name, members);
+        Ok(())
+    }
+
+    /// Remove a recipient group.
+    pub fn remove_group(&mut self, name: &str) -> PyResult<()> {
+        self.groups
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("No group named {name}").into())
+    }
+
+    /// The recipient groups, mapping group names to member fingerprints.
+    #[getter]
+    pub fn groups(&self) -> BTreeMap<String, Vec<String>> {
+        self.groups
+            .iter()
+            .map(|(name, members)| {
+                let members = members
+                    .iter()
+                    .map(|fingerprint| format!("{fingerprint:x}"))
+                    .collect();
+                (name.clone(), members)
+            })
+            .collect()
+    }
+
+    /// Resolve a group name or email address to the matching certificates.
+    pub fn resolve(&self, name: &str) -> PyResult<Vec<Cert>> {
+        Ok(self.resolve_name(name)?)
+    }
+
+    fn __len__(&self) -> usize {
+        self.certs.len()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<Keyring certs={} groups={}>",
+            self.certs.len(),
+            self.groups.len()
+        )
+    }
+}
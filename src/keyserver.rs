@@ -0,0 +1,152 @@
+use anyhow::anyhow;
+use pyo3::prelude::*;
+use sequoia_openpgp::KeyHandle;
+use sequoia_openpgp::cert::CertParser;
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::serialize::SerializeInto;
+
+use crate::cert::Cert;
+use crate::http::{percent_encode, request};
+
+/// The protocol spoken by a keyserver.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[pyclass(eq, from_py_object)]
+pub enum KeyServerProtocol {
+    /// HTTP Keyserver Protocol (`/pks/lookup` and `/pks/add`)
+    HKP,
+    /// Verifying Keyserver API used by keys.openpgp.org (`/vks/v1/...`)
+    VKS,
+}
+
+enum Query {
+    Email(String),
+    Handle(KeyHandle),
+}
+
+impl Query {
+    fn parse(query: &str) -> anyhow::Result<Self> {
+        if query.contains('@') {
+            return Ok(Self::Email(query.to_owned()));
+        }
+        let hex = query.trim_start_matches("0x").replace(' ', "");
+        hex.parse()
+            .map(Self::Handle)
+            .map_err(|_| anyhow!("Expected a fingerprint, key ID or email address: {query}"))
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len() + 2);
+    encoded.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            c if c.is_control() => encoded.push_str(&format!("\\u{:04x}", c as u32)),
+            c => encoded.push(c),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+/// A keyserver client supporting the HKP and VKS protocols.
+///
+/// The `url` is the base URL of the keyserver, e.g. `https://keys.openpgp.org`.
+/// The optional `transport` callable is called with the URL (and, for uploads,
+/// the request body and its content type) and must return the response body
+/// as bytes; by default `urllib` is used.
+#[pyclass]
+pub struct KeyServer {
+    url: String,
+    protocol: KeyServerProtocol,
+    transport: Option<Py<PyAny>>,
+}
+
+impl KeyServer {
+    fn lookup_url(&self, query: &Query) -> String {
+        let url = &self.url;
+        match (self.protocol, query) {
+            (KeyServerProtocol::HKP, Query::Email(email)) => format!(
+                "{url}/pks/lookup?op=get&options=mr&search={}",
+                percent_encode(email)
+            ),
+            (KeyServerProtocol::HKP, Query::Handle(handle)) => {
+                format!("{url}/pks/lookup?op=get&options=mr&search=0x{handle:X}")
+            }
+            (KeyServerProtocol::VKS, Query::Email(email)) => {
+                format!("{url}/vks/v1/by-email/{}", percent_encode(email))
+            }
+            (KeyServerProtocol::VKS, Query::Handle(KeyHandle::Fingerprint(fpr))) => {
+                format!("{url}/vks/v1/by-fingerprint/{fpr:X}")
+            }
+            (KeyServerProtocol::VKS, Query::Handle(KeyHandle::KeyID(id))) => {
+                format!("{url}/vks/v1/by-keyid/{id:X}")
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl KeyServer {
+    /// Create a client for the keyserver at `url`.
+    #[new]
+    #[pyo3(signature = (url="https://keys.openpgp.org", protocol=KeyServerProtocol::VKS, *, transport=None))]
+    pub fn new(url: &str, protocol: KeyServerProtocol, transport: Option<Py<PyAny>>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').into(),
+            protocol,
+            transport,
+        }
+    }
+
+    /// Retrieve certificates by fingerprint, key ID or email address.
+    pub fn get(&self, py: Python<'_>, query: &str) -> PyResult<Vec<Cert>> {
+        let url = self.lookup_url(&Query::parse(query)?);
+        let response = request(py, &self.transport, &url, None)?;
+        let mut certs = vec![];
+        for cert in CertParser::from_bytes(&response)? {
+            certs.push(cert?.into());
+        }
+        Ok(certs)
+    }
+
+    /// Upload a certificate to the keyserver.
+    ///
+    /// Only the public, exportable parts of the certificate are sent.
+    /// Returns the response body sent back by the server.
+    pub fn send(&self, py: Python<'_>, cert: &Cert) -> PyResult<String> {
+        let armored = String::from_utf8(cert.cert().armored().export_to_vec()?)?;
+        let (url, body, content_type) = match self.protocol {
+            KeyServerProtocol::HKP => (
+                format!("{}/pks/add", self.url),
+                format!("keytext={}", percent_encode(&armored)),
+                "application/x-www-form-urlencoded",
+            ),
+            KeyServerProtocol::VKS => (
+                format!("{}/vks/v1/upload", self.url),
+                format!("{{\"keytext\":{}}}", json_string(&armored)),
+                "application/json",
+            ),
+        };
+        let response = request(
+            py,
+            &self.transport,
+            &url,
+            Some((body.as_bytes(), content_type)),
+        )?;
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+
+    /// The base URL of the keyserver.
+    #[getter]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("<KeyServer url={}>", self.url)
+    }
+}
@@ -1,24 +1,36 @@
 use std::borrow::Cow;
 
+mod autocrypt;
 mod cert;
+mod dane;
 mod decrypt;
 mod encrypt;
+mod http;
+mod inspect;
+mod keyring;
+mod keyserver;
 mod notation;
 mod packet;
+mod pyio;
+mod reader;
+mod reencrypt;
 mod sign;
 mod signature;
 mod signer;
 mod types;
 mod user_id;
 mod verify;
+mod wkd;
 
 use pyo3::prelude::*;
 use sequoia_openpgp::armor::Kind;
-use sequoia_openpgp::packet::Packet;
-use sequoia_openpgp::parse::stream::GoodChecksum;
-use sequoia_openpgp::serialize::stream::Armorer;
+use sequoia_openpgp::packet::{Literal, Packet};
+use sequoia_openpgp::parse::stream::{GoodChecksum, MessageLayer};
+use sequoia_openpgp::serialize::stream::{Armorer, Compressor, LiteralWriter};
 use sequoia_openpgp::serialize::{Marshal, stream::Message};
+use sequoia_openpgp::types::{CompressionAlgorithm, HashAlgorithm};
 
+use crate::decrypt::DecryptedWith;
 use crate::types::ArmorKind;
 
 pub(crate) fn serialize<T>(p: Packet, armor_kind: T) -> sequoia_openpgp::Result<Vec<u8>>
@@ -35,6 +47,30 @@ where
     Ok(sink)
 }
 
+/// Adds a compression layer to the writer stack if `algo` is given.
+pub(crate) fn compress(
+    message: Message<'_>,
+    algo: Option<types::CompressionAlgorithm>,
+) -> anyhow::Result<Message<'_>> {
+    let Some(algo) = algo else {
+        return Ok(message);
+    };
+    let algo = CompressionAlgorithm::from(algo);
+    if !algo.is_supported() {
+        return Err(anyhow::anyhow!(
+            "{algo} compression is not supported by this build"
+        ));
+    }
+    Compressor::new(message).algo(algo).build()
+}
+
+/// Computes the `algo` digest of `data`.
+pub(crate) fn digest(algo: HashAlgorithm, data: &[u8]) -> sequoia_openpgp::Result<Vec<u8>> {
+    let mut context = algo.context()?.for_digest();
+    context.update(data);
+    context.into_digest()
+}
+
 /// A verified valid signature, containing the certificate and signing key fingerprints.
 #[pyclass(skip_from_py_object)]
 #[derive(Debug, Clone)]
@@ -75,6 +111,157 @@ impl ValidSig {
     }
 }
 
+/// The kind of a message layer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LayerKind {
+    Encryption,
+    Compression,
+    SignatureGroup,
+}
+
+/// A layer of a decrypted or verified message.
+///
+/// Layers are listed from the outermost to the innermost one.
+#[pyclass(name = "MessageLayer", skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct PyMessageLayer {
+    kind: LayerKind,
+    cipher: Option<types::SymmetricAlgorithm>,
+    aead: Option<types::AEADAlgorithm>,
+    compression: Option<types::CompressionAlgorithm>,
+    valid_sigs: Vec<ValidSig>,
+    errors: Vec<String>,
+}
+
+impl PyMessageLayer {
+    fn new(kind: LayerKind) -> Self {
+        Self {
+            kind,
+            cipher: None,
+            aead: None,
+            compression: None,
+            valid_sigs: vec![],
+            errors: vec![],
+        }
+    }
+}
+
+impl From<MessageLayer<'_>> for PyMessageLayer {
+    fn from(layer: MessageLayer<'_>) -> Self {
+        match layer {
+            MessageLayer::Encryption {
+                sym_algo,
+                aead_algo,
+            } => Self {
+                cipher: sym_algo.try_into().ok(),
+                aead: aead_algo.and_then(|aead| aead.try_into().ok()),
+                ..Self::new(LayerKind::Encryption)
+            },
+            MessageLayer::Compression { algo } => Self {
+                compression: algo.try_into().ok(),
+                ..Self::new(LayerKind::Compression)
+            },
+            MessageLayer::SignatureGroup { results } => {
+                let mut layer = Self::new(LayerKind::SignatureGroup);
+                for result in results {
+                    match result {
+                        Ok(checksum) => layer.valid_sigs.push(checksum.into()),
+                        Err(error) => layer.errors.push(error.to_string()),
+                    }
+                }
+                layer
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl PyMessageLayer {
+    /// The kind of the layer: `"encryption"`, `"compression"` or `"signature_group"`.
+    #[getter]
+    pub fn kind(&self) -> &'static str {
+        match self.kind {
+            LayerKind::Encryption => "encryption",
+            LayerKind::Compression => "compression",
+            LayerKind::SignatureGroup => "signature_group",
+        }
+    }
+
+    /// The symmetric cipher of an encryption layer.
+    #[getter]
+    pub fn cipher(&self) -> Option<types::SymmetricAlgorithm> {
+        self.cipher
+    }
+
+    /// The AEAD mode of an encryption layer, or `None` for SEIPDv1.
+    #[getter]
+    pub fn aead(&self) -> Option<types::AEADAlgorithm> {
+        self.aead
+    }
+
+    /// The compression algorithm of a compression layer.
+    #[getter]
+    pub fn compression(&self) -> Option<types::CompressionAlgorithm> {
+        self.compression
+    }
+
+    /// The valid signatures of a signature group.
+    #[getter]
+    pub fn valid_sigs(&self) -> Vec<ValidSig> {
+        self.valid_sigs.clone()
+    }
+
+    /// Why the other signatures of a signature group could not be verified.
+    #[getter]
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("<MessageLayer kind={}>", self.kind())
+    }
+}
+
+/// The metadata of a Literal Data packet.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LiteralMetadata {
+    pub filename: Option<String>,
+    pub date: Option<chrono::DateTime<chrono::Utc>>,
+    pub format: Option<types::DataFormat>,
+}
+
+impl LiteralMetadata {
+    /// Reads the metadata of a parsed Literal Data packet.
+    pub fn from_packet(literal: &Literal) -> Self {
+        Self {
+            filename: literal
+                .filename()
+                .map(|f| String::from_utf8_lossy(f).into_owned()),
+            date: literal.date().map(Into::into),
+            format: literal.format().try_into().ok(),
+        }
+    }
+
+    /// Adds the literal data layer carrying this metadata to the writer stack.
+    pub fn writer<'a>(&self, message: Message<'a>) -> anyhow::Result<Message<'a>> {
+        let mut writer = LiteralWriter::new(message);
+        if let Some(format) = self.format {
+            writer = writer.format(format.into());
+        }
+        if let Some(filename) = &self.filename {
+            writer = writer.filename(filename)?;
+        }
+        if let Some(date) = self.date {
+            writer = writer.date(date)?;
+        }
+        writer.build()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filename.is_none() && self.date.is_none() && self.format.is_none()
+    }
+}
+
 /// The result of a decryption or verification operation.
 ///
 /// Contains the decrypted/verified content (if available) and any valid signatures found.
@@ -83,6 +270,16 @@ impl ValidSig {
 pub struct Decrypted {
     valid_sigs: Vec<ValidSig>,
     content: Option<Vec<u8>>,
+    literal: LiteralMetadata,
+    session_key: Option<String>,
+    decrypted_with: Option<DecryptedWith>,
+    layers: Vec<PyMessageLayer>,
+}
+
+impl Decrypted {
+    fn layer(&self, kind: LayerKind) -> Option<&PyMessageLayer> {
+        self.layers.iter().find(|layer| layer.kind == kind)
+    }
 }
 
 #[pymethods]
@@ -96,10 +293,91 @@ impl Decrypted {
     }
 
     /// The list of valid signatures found during verification.
+    ///
+    /// Signatures outside of the encryption do not sign the plaintext and are
+    /// only reported in `layers`.
     #[getter]
     pub fn valid_sigs(&self) -> Vec<ValidSig> {
         self.valid_sigs.clone()
     }
+
+    /// The file name stored with the content, or `None` if the sender did not set one.
+    #[getter]
+    pub fn filename(&self) -> Option<&str> {
+        self.literal.filename.as_deref()
+    }
+
+    /// The date stored with the content, or `None` if the sender did not set one.
+    #[getter]
+    pub fn literal_date(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.literal.date
+    }
+
+    /// The format of the content, or `None` for detached signatures.
+    #[getter]
+    pub fn format(&self) -> Option<types::DataFormat> {
+        self.literal.format
+    }
+
+    /// The session key of the message in the `ALGO:HEX` form, if it was requested.
+    ///
+    /// It decrypts this message only and can be passed as `session_keys` to `decrypt`.
+    #[getter]
+    pub fn session_key(&self) -> Option<&str> {
+        self.session_key.as_deref()
+    }
+
+    /// How the message was decrypted: `"key"`, `"password"` or `"session_key"`.
+    ///
+    /// `None` if the message was not encrypted.
+    #[getter]
+    pub fn decryption_method(&self) -> Option<&'static str> {
+        self.decrypted_with.as_ref().map(|with| match with {
+            DecryptedWith::Key(_) => "key",
+            DecryptedWith::Password => "password",
+            DecryptedWith::SessionKey => "session_key",
+        })
+    }
+
+    /// The fingerprint of the (sub)key that decrypted the message, if any.
+    #[getter]
+    pub fn decryption_key(&self) -> Option<&str> {
+        match &self.decrypted_with {
+            Some(DecryptedWith::Key(fingerprint)) => Some(fingerprint),
+            _ => None,
+        }
+    }
+
+    /// The symmetric cipher the message was encrypted with.
+    #[getter]
+    pub fn cipher(&self) -> Option<types::SymmetricAlgorithm> {
+        self.layer(LayerKind::Encryption)?.cipher
+    }
+
+    /// The AEAD mode the message was encrypted with, or `None` for SEIPDv1.
+    #[getter]
+    pub fn aead(&self) -> Option<types::AEADAlgorithm> {
+        self.layer(LayerKind::Encryption)?.aead
+    }
+
+    /// The version of the encrypted data packet: 1, or 2 for AEAD.
+    #[getter]
+    pub fn seipd_version(&self) -> Option<u8> {
+        let layer = self.layer(LayerKind::Encryption)?;
+        Some(if layer.aead.is_some() { 2 } else { 1 })
+    }
+
+    /// The compression algorithm of the message, if it was compressed.
+    #[getter]
+    pub fn compression(&self) -> Option<types::CompressionAlgorithm> {
+        self.layer(LayerKind::Compression)?.compression
+    }
+
+    /// The layers of the message from the outermost to the innermost one.
+    #[getter]
+    pub fn layers(&self) -> Vec<PyMessageLayer> {
+        self.layers.clone()
+    }
 }
 
 fn runtime_err<E: std::fmt::Display>(e: E) -> pyo3::PyErr {
@@ -129,6 +407,8 @@ pub mod pysequoia {
     #[pymodule_export]
     pub use super::Decrypted;
     #[pymodule_export]
+    pub use super::PyMessageLayer;
+    #[pymodule_export]
     pub use super::armor;
     #[pymodule_export]
     pub use super::cert::Cert;
@@ -139,18 +419,54 @@ pub mod pysequoia {
     #[pymodule_export]
     pub use super::cert::secret::Tsk;
     #[pymodule_export]
+    pub use super::cert::unlock::KeyInfo;
+    #[pymodule_export]
     pub use super::decrypt::PyDecryptor;
     #[pymodule_export]
+    pub use super::decrypt::SkeskInfo;
+    #[pymodule_export]
     pub use super::decrypt::decrypt;
     #[pymodule_export]
     pub use super::decrypt::decrypt_file;
     #[pymodule_export]
+    pub use super::encrypt::EncryptionReport;
+    #[pymodule_export]
+    pub use super::encrypt::Padding;
+    #[pymodule_export]
+    pub use super::encrypt::PyEncryptor;
+    #[pymodule_export]
+    pub use super::encrypt::PyRecipient;
+    #[pymodule_export]
+    pub use super::encrypt::RecipientReport;
+    #[pymodule_export]
     pub use super::encrypt::encrypt;
     #[pymodule_export]
     pub use super::encrypt::encrypt_file;
     #[pymodule_export]
+    pub use super::inspect::MessageInfo;
+    #[pymodule_export]
+    pub use super::inspect::PkeskInfo;
+    #[pymodule_export]
+    pub use super::inspect::inspect_message;
+    #[pymodule_export]
+    pub use super::keyring::Keyring;
+    #[pymodule_export]
+    pub use super::keyserver::KeyServer;
+    #[pymodule_export]
+    pub use super::keyserver::KeyServerProtocol;
+    #[pymodule_export]
     pub use super::notation::Notation;
     #[pymodule_export]
+    pub use super::reader::MessageReader;
+    #[pymodule_export]
+    pub use super::reader::open_decrypt;
+    #[pymodule_export]
+    pub use super::reader::open_verify;
+    #[pymodule_export]
+    pub use super::reencrypt::reencrypt;
+    #[pymodule_export]
+    pub use super::reencrypt::reencrypt_file;
+    #[pymodule_export]
     pub use super::sign::SignatureMode;
     #[pymodule_export]
     pub use super::sign::sign;
@@ -161,16 +477,50 @@ pub mod pysequoia {
     #[pymodule_export]
     pub use super::signer::PySigner;
     #[pymodule_export]
+    pub use super::types::AEADAlgorithm;
+    #[pymodule_export]
     pub use super::types::ArmorKind;
     #[pymodule_export]
+    pub use super::types::CompressionAlgorithm;
+    #[pymodule_export]
     pub use super::types::EncryptionAlgorithm;
     #[pymodule_export]
+    pub use super::types::S2K;
+    #[pymodule_export]
     pub use super::types::SigningAlgorithm;
     #[pymodule_export]
+    pub use super::types::SymmetricAlgorithm;
+    #[pymodule_export]
     pub use super::user_id::UserId;
     #[pymodule_export]
+    pub use super::verify::SignaturePolicy;
+    #[pymodule_export]
     pub use super::verify::verify;
 
+    #[pymodule]
+    pub mod autocrypt {
+        #[pymodule_export]
+        pub use crate::autocrypt::AutocryptHeader;
+        #[pymodule_export]
+        pub use crate::autocrypt::generate_header;
+        #[pymodule_export]
+        pub use crate::autocrypt::generate_setup_message;
+        #[pymodule_export]
+        pub use crate::autocrypt::parse_header;
+        #[pymodule_export]
+        pub use crate::autocrypt::parse_setup_message;
+    }
+
+    #[pymodule]
+    pub mod dane {
+        #[pymodule_export]
+        pub use crate::dane::generate_record;
+        #[pymodule_export]
+        pub use crate::dane::owner_name;
+        #[pymodule_export]
+        pub use crate::dane::parse_record;
+    }
+
     #[pymodule]
     pub mod packet {
         #[pymodule_export]
@@ -190,4 +540,20 @@ pub mod pysequoia {
         #[pymodule_export]
         pub use crate::types::Tag;
     }
+
+    #[pymodule]
+    pub mod wkd {
+        #[pymodule_export]
+        pub use crate::wkd::advanced_url;
+        #[pymodule_export]
+        pub use crate::wkd::direct_url;
+        #[pymodule_export]
+        pub use crate::wkd::fetch;
+        #[pymodule_export]
+        pub use crate::wkd::generate;
+        #[pymodule_export]
+        pub use crate::wkd::local_part_hash;
+        #[pymodule_export]
+        pub use crate::wkd::parse_response;
+    }
 }
@@ -0,0 +1,61 @@
+use std::io;
+
+use pyo3::prelude::*;
+
+/// Adapts a Python binary file-like object (anything with a `write` method)
+/// to [`io::Write`].
+pub struct PyWriter {
+    inner: Py<PyAny>,
+}
+
+impl PyWriter {
+    pub fn new(inner: Py<PyAny>) -> Self {
+        Self { inner }
+    }
+}
+
+impl io::Write for PyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written: Option<usize> =
+            Python::attach(|py| self.inner.call_method1(py, "write", (buf,))?.extract(py))?;
+        // Objects not reporting the number of bytes written are assumed to take everything.
+        Ok(written.unwrap_or(buf.len()))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Python::attach(|py| {
+            if self.inner.bind(py).hasattr("flush")? {
+                self.inner.call_method0(py, "flush")?;
+            }
+            Ok::<_, PyErr>(())
+        })?;
+        Ok(())
+    }
+}
+
+/// Adapts a Python binary file-like object (anything with a `read` method)
+/// to [`io::Read`].
+pub struct PyReader {
+    inner: Py<PyAny>,
+}
+
+impl PyReader {
+    pub fn new(inner: Py<PyAny>) -> Self {
+        Self { inner }
+    }
+}
+
+impl io::Read for PyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data: Vec<u8> = Python::attach(|py| {
+            self.inner
+                .call_method1(py, "read", (buf.len(),))?
+                .extract(py)
+        })?;
+        if data.len() > buf.len() {
+            return Err(io::Error::other("read() returned more data than requested"));
+        }
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::parse::stream::{Decryptor, DecryptorBuilder, Verifier, VerifierBuilder};
+use sequoia_openpgp::policy::StandardPolicy;
+
+use crate::ValidSig;
+use crate::decrypt::{Decryptors, PyDecryptor};
+use crate::pyio::PyReader;
+use crate::verify::PyVerifier;
+
+static POLICY: StandardPolicy<'static> = StandardPolicy::new();
+
+/// Size of the chunks returned when iterating over a `MessageReader`.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The source of a streamed message: a path or a binary file-like object.
+#[derive(FromPyObject)]
+pub enum Source {
+    Path(PathBuf),
+    Reader(Py<PyAny>),
+}
+
+enum Stream {
+    Decryptor(Decryptor<'static, PyDecryptor>),
+    Verifier(Verifier<'static, PyVerifier>),
+}
+
+impl Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Decryptor(decryptor) => decryptor.read(buf),
+            Stream::Verifier(verifier) => verifier.read(buf),
+        }
+    }
+
+    fn valid_sigs(self) -> Vec<ValidSig> {
+        match self {
+            Stream::Decryptor(decryptor) => decryptor.into_helper().valid_sigs(),
+            Stream::Verifier(verifier) => verifier.into_helper().valid_sigs(),
+        }
+    }
+}
+
+/// A readable stream of plaintext from a decrypted or verified message.
+///
+/// Returned by `open_decrypt` and `open_verify`. Supports `read`, iteration
+/// over chunks and use as a context manager. Signatures are checked when the
+/// end of the message is reached: a failed check is raised by the final read
+/// and again by every later read, and `valid_sigs` is only available once the
+/// whole message has been read successfully.
+#[pyclass]
+pub struct MessageReader {
+    stream: Option<Stream>,
+    valid_sigs: Option<Vec<ValidSig>>,
+    /// The error that ended reading; the rest of the plaintext is never returned.
+    error: Option<PyErr>,
+}
+
+impl MessageReader {
+    fn new(stream: Stream) -> Self {
+        Self {
+            stream: Some(stream),
+            valid_sigs: None,
+            error: None,
+        }
+    }
+
+    fn read_chunk(&mut self, size: usize) -> PyResult<Vec<u8>> {
+        if let Some(error) = &self.error {
+            return Err(Python::attach(|py| error.clone_ref(py)));
+        }
+        let Some(stream) = &mut self.stream else {
+            if self.valid_sigs.is_some() {
+                return Ok(vec![]);
+            }
+            return Err(PyValueError::new_err("MessageReader is closed"));
+        };
+
+        let mut buf = vec![0; size];
+        let mut filled = 0;
+        while filled < size {
+            let read = match stream.read(&mut buf[filled..]) {
+                Ok(read) => read,
+                Err(err) => {
+                    self.stream = None;
+                    let error = PyErr::from(err);
+                    self.error = Some(Python::attach(|py| error.clone_ref(py)));
+                    return Err(error);
+                }
+            };
+            if read == 0 {
+                if let Some(stream) = self.stream.take() {
+                    self.valid_sigs = Some(stream.valid_sigs());
+                }
+                break;
+            }
+            filled += read;
+        }
+        buf.truncate(filled);
+        Ok(buf)
+    }
+}
+
+#[pymethods]
+impl MessageReader {
+    /// Read up to `size` bytes of plaintext, or everything that is left if `size` is negative.
+    ///
+    /// Returns an empty `bytes` object at the end of the message.
+    #[pyo3(signature = (size=-1))]
+    pub fn read(&mut self, size: isize) -> PyResult<Cow<'static, [u8]>> {
+        if let Ok(size) = usize::try_from(size) {
+            return Ok(self.read_chunk(size)?.into());
+        }
+        let mut content = vec![];
+        loop {
+            let chunk = self.read_chunk(CHUNK_SIZE)?;
+            if chunk.is_empty() {
+                return Ok(content.into());
+            }
+            content.extend(chunk);
+        }
+    }
+
+    /// The list of valid signatures, available once the whole message has been read.
+    #[getter]
+    pub fn valid_sigs(&self) -> PyResult<Vec<ValidSig>> {
+        self.valid_sigs.clone().ok_or_else(|| {
+            PyValueError::new_err("Signatures are available only after reading the whole message")
+        })
+    }
+
+    /// Stop reading and release the underlying source.
+    pub fn close(&mut self) {
+        self.stream = None;
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<Cow<'static, [u8]>>> {
+        let chunk = self.read_chunk(CHUNK_SIZE)?;
+        Ok((!chunk.is_empty()).then(|| chunk.into()))
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<Py<PyAny>>,
+        _exc_value: Option<Py<PyAny>>,
+        _traceback: Option<Py<PyAny>>,
+    ) -> bool {
+        self.close();
+        false
+    }
+}
+
+/// Open an encrypted message for reading its plaintext incrementally.
+///
+/// The `source` is a path or a binary file-like object. Takes the same
+/// parameters as `decrypt`, but returns a `MessageReader` instead of
+/// holding the whole plaintext in memory.
+#[pyfunction]
+#[pyo3(signature = (source, decryptor=None, store=None, passwords=vec![], *, session_keys=vec![], password_callback=None))]
+pub fn open_decrypt(
+    source: Source,
+    decryptor: Option<Decryptors>,
+    store: Option<Py<PyAny>>,
+    passwords: Vec<String>,
+    session_keys: Vec<String>,
+    password_callback: Option<Py<PyAny>>,
+) -> PyResult<MessageReader> {
+    let helper =
+        crate::decrypt::helper(decryptor, store, passwords, session_keys, password_callback)?;
+    let builder = match source {
+        Source::Path(path) => DecryptorBuilder::from_file(path)?,
+        Source::Reader(reader) => DecryptorBuilder::from_reader(PyReader::new(reader))?,
+    };
+    let decryptor = builder.with_policy(&POLICY, None, helper)?;
+    Ok(MessageReader::new(Stream::Decryptor(decryptor)))
+}
+
+/// Open an inline-signed message for reading its content incrementally.
+///
+/// The `source` is a path or a binary file-like object. The `store` callback
+/// is called with a list of key ID strings and must return a list of `Cert` objects.
+#[pyfunction]
+pub fn open_verify(source: Source, store: Py<PyAny>) -> PyResult<MessageReader> {
+    let builder = match source {
+        Source::Path(path) => VerifierBuilder::from_file(path)?,
+        Source::Reader(reader) => VerifierBuilder::from_reader(PyReader::new(reader))?,
+    };
+    let verifier = builder.with_policy(&POLICY, None, PyVerifier::from_callback(store))?;
+    Ok(MessageReader::new(Stream::Verifier(verifier)))
+}
@@ -0,0 +1,358 @@
+#![expect(
+    clippy::too_many_arguments,
+    reason = "re-encryption options are keyword arguments in Python"
+)]
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, anyhow};
+use pyo3::prelude::*;
+use sequoia_openpgp::Profile;
+use sequoia_openpgp::armor;
+use sequoia_openpgp::crypto::{Password, S2K, SessionKey};
+use sequoia_openpgp::packet::skesk::{SKESK4, SKESK6};
+use sequoia_openpgp::packet::{Body, PKESK, Packet, SEIP, SKESK, Tag};
+use sequoia_openpgp::parse::buffered_reader::BufferedReader;
+use sequoia_openpgp::parse::{PacketParser, PacketParserResult, Parse};
+use sequoia_openpgp::serialize::stream::{ArbitraryWriter, Message};
+use sequoia_openpgp::serialize::{Marshal, MarshalInto};
+use sequoia_openpgp::types::{AEADAlgorithm, SymmetricAlgorithm};
+
+use crate::cert::Cert;
+use crate::decrypt::{Decryptors, PyDecryptor};
+use crate::encrypt::cert_recipient_keys;
+use crate::types;
+
+/// The session key of a message together with the parameters of its
+/// encrypted data packet.
+struct SessionKeyInfo {
+    algo: Option<SymmetricAlgorithm>,
+    session_key: SessionKey,
+    /// The cipher and AEAD mode of a SEIPDv2 packet.
+    aead: Option<(SymmetricAlgorithm, AEADAlgorithm)>,
+}
+
+/// Copies the header fields of `seip` into a packet with the given `body`.
+fn with_body(seip: &SEIP, body: Vec<u8>) -> anyhow::Result<SEIP> {
+    let mut seip = seip.clone();
+    match &mut seip {
+        SEIP::V1(seip) => seip.set_body(Body::Unprocessed(body)),
+        SEIP::V2(seip) => seip.set_body(Body::Unprocessed(body)),
+        _ => return Err(anyhow!("Unsupported SEIP version {}", seip.version())),
+    };
+    Ok(seip)
+}
+
+/// Checks `session_key` against the start of the encrypted data.
+///
+/// The `prefix` is wrapped in a copy of the encrypted data packet, so that the
+/// packet parser checks the key like it does when decrypting the message.
+fn session_key_fits(
+    seip: &SEIP,
+    prefix: &[u8],
+    algo: Option<SymmetricAlgorithm>,
+    session_key: &SessionKey,
+) -> anyhow::Result<()> {
+    let packet = Packet::SEIP(with_body(seip, prefix.to_vec())?).to_vec()?;
+    match PacketParser::from_bytes(&packet)? {
+        PacketParserResult::Some(mut pp) => pp.decrypt(algo, session_key),
+        PacketParserResult::EOF(_) => Err(anyhow!("Encrypted data packet is missing")),
+    }
+}
+
+/// Recovers the session key of the encrypted data packet `pp` is positioned at.
+///
+/// Only the start of the encrypted data is read to check candidate keys; it is
+/// not consumed, so the packet can still be copied as a whole.
+fn find_session_key(
+    pp: &mut PacketParser,
+    seip: &SEIP,
+    pkesks: &[PKESK],
+    skesks: &[SKESK],
+    decryptor: &mut PyDecryptor,
+) -> anyhow::Result<SessionKeyInfo> {
+    let (amount, aead) = match seip {
+        // The largest block size plus the two quick check octets, followed
+        // by the MDC packet the parser holds back.
+        SEIP::V1(_) => (16 + 2 + 22, None),
+        // The first chunk and its tag, plus the final tag.
+        SEIP::V2(seip) => (
+            usize::try_from(seip.chunk_digest_size()? + seip.aead().digest_size()? as u64)?,
+            Some((seip.symmetric_algo(), seip.aead())),
+        ),
+        _ => return Err(anyhow!("Unsupported SEIP version {}", seip.version())),
+    };
+    let data = pp.data(amount)?;
+    let prefix = data[..data.len().min(amount)].to_vec();
+
+    let sym_algo = aead.map(|(cipher, _)| cipher);
+    let (algo, session_key) = decryptor
+        .find_session_key(pkesks, skesks, sym_algo, &mut |algo, session_key| {
+            session_key_fits(seip, &prefix, algo, session_key).is_ok()
+        })?
+        .ok_or_else(|| anyhow!("No key to decrypt message"))?;
+    Ok(SessionKeyInfo {
+        algo,
+        session_key,
+        aead,
+    })
+}
+
+/// The changes to the key packets of a message.
+struct Changes {
+    add_recipients: Vec<Cert>,
+    remove_recipients: Vec<Cert>,
+    passwords: Vec<String>,
+    s2k: Option<types::S2K>,
+}
+
+impl Changes {
+    /// Creates the key packets of the re-encrypted message from the original ones.
+    fn apply(
+        &self,
+        pkesks: Vec<PKESK>,
+        skesks: Vec<SKESK>,
+        info: &SessionKeyInfo,
+    ) -> anyhow::Result<Vec<Packet>> {
+        let session_key = &info.session_key;
+        let removed = self
+            .remove_recipients
+            .iter()
+            .flat_map(|cert| cert.cert().keys().map(|key| key.key().key_handle()))
+            .collect::<Vec<_>>();
+
+        let mut pkesks = pkesks
+            .into_iter()
+            .filter(|pkesk| {
+                !pkesk
+                    .recipient()
+                    .is_some_and(|recipient| removed.iter().any(|key| recipient.aliases(key)))
+            })
+            .collect::<Vec<_>>();
+
+        for cert in self.add_recipients.iter() {
+            for recipient_key in cert_recipient_keys(cert)? {
+                let key = recipient_key.key();
+                let present = pkesks.iter().any(|pkesk| {
+                    pkesk
+                        .recipient()
+                        .is_some_and(|recipient| recipient.aliases(key.key_handle()))
+                });
+                if present {
+                    continue;
+                }
+                let pkesk = match info.aead {
+                    None => {
+                        let algo = info.algo.context("Unknown symmetric algorithm")?;
+                        recipient_key.pkesk(algo, false, session_key)?
+                    }
+                    Some((cipher, aead)) => {
+                        recipient_key.check_algorithms(None, Some(aead))?;
+                        recipient_key.pkesk(cipher, true, session_key)?
+                    }
+                };
+                pkesks.push(pkesk);
+            }
+        }
+
+        let mut skesks = skesks;
+        for password in self.passwords.iter() {
+            let password = Password::from(password.as_str());
+            let s2k = match self.s2k {
+                Some(s2k) => s2k.try_into()?,
+                None => S2K::default(),
+            };
+            let skesk = match info.aead {
+                None => {
+                    if matches!(s2k, S2K::Argon2 { .. }) {
+                        return Err(anyhow!("Argon2 requires a SEIPDv2 message"));
+                    }
+                    let algo = info.algo.context("Unknown symmetric algorithm")?;
+                    SKESK::V4(SKESK4::with_password(
+                        algo,
+                        algo,
+                        s2k,
+                        session_key,
+                        &password,
+                    )?)
+                }
+                Some((cipher, aead)) => SKESK::V6(SKESK6::with_password(
+                    cipher,
+                    cipher,
+                    aead,
+                    s2k,
+                    session_key,
+                    &password,
+                )?),
+            };
+            skesks.push(skesk);
+        }
+
+        if pkesks.is_empty() && skesks.is_empty() {
+            return Err(anyhow!("The message would have no recipients left"));
+        }
+        Ok(pkesks
+            .into_iter()
+            .map(Packet::PKESK)
+            .chain(skesks.into_iter().map(Packet::SKESK))
+            .collect())
+    }
+}
+
+/// Writes a message to `sink`, armored if `armor` is set.
+///
+/// SEIPDv2 messages are armored without a checksum, as RFC 9580 requires.
+fn write_message<W>(
+    sink: W,
+    armor: bool,
+    seipdv2: bool,
+    write: impl FnOnce(Message) -> anyhow::Result<()>,
+) -> anyhow::Result<()>
+where
+    W: Write + Send + Sync,
+{
+    if !armor {
+        return write(Message::new(sink));
+    }
+    let mut writer = armor::Writer::new(sink, armor::Kind::Message)?;
+    writer.set_profile(if seipdv2 {
+        Profile::RFC9580
+    } else {
+        Profile::RFC4880
+    })?;
+    write(Message::new(&mut writer))?;
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Writes the message parsed by `ppr` with the key packets changed to `sink`.
+///
+/// The encrypted data packet is copied as it is read.
+fn reencrypt_into<W>(
+    mut ppr: PacketParserResult,
+    decryptor: &mut PyDecryptor,
+    changes: &Changes,
+    sink: W,
+    armor: bool,
+) -> anyhow::Result<()>
+where
+    W: Write + Send + Sync,
+{
+    let mut pkesks = vec![];
+    let mut skesks = vec![];
+    while let PacketParserResult::Some(mut pp) = ppr {
+        let seip = match &pp.packet {
+            Packet::PKESK(pkesk) => {
+                pkesks.push(pkesk.clone());
+                None
+            }
+            Packet::SKESK(skesk) => {
+                skesks.push(skesk.clone());
+                None
+            }
+            Packet::Marker(_) => None,
+            Packet::SEIP(seip) => Some(seip.clone()),
+            packet => return Err(anyhow!("Unexpected {} packet", packet.tag())),
+        };
+        if let Some(seip) = seip {
+            let info = find_session_key(&mut pp, &seip, &pkesks, &skesks, decryptor)?;
+            let packets = changes.apply(pkesks, skesks, &info)?;
+            write_message(sink, armor, info.aead.is_some(), |mut message| {
+                for packet in packets {
+                    packet.serialize(&mut message)?;
+                }
+                // The header fields are written first, followed by the encrypted data as is.
+                let mut writer = ArbitraryWriter::new(message, Tag::SEIP)?;
+                with_body(&seip, vec![])?.serialize(&mut writer)?;
+                std::io::copy(&mut pp, &mut writer)?;
+                writer.finalize()
+            })?;
+            if let PacketParserResult::Some(pp) = pp.next()?.1 {
+                return Err(anyhow!(
+                    "Unexpected {} packet after the encrypted data",
+                    pp.packet.tag()
+                ));
+            }
+            return Ok(());
+        }
+        ppr = pp.next()?.1;
+    }
+    Err(anyhow!("Message does not contain encrypted data"))
+}
+
+/// Change the recipients of an encrypted message without re-encrypting its content.
+///
+/// The `decryptor` is used to recover the session key only. New PKESK packets are
+/// created for `add_recipients` and new SKESK packets for `passwords`, while PKESK
+/// packets addressed to any key of `remove_recipients` are dropped. The encrypted
+/// data packet is copied unchanged, so recipients added to a SEIPDv2 message must
+/// support SEIPDv2. The `s2k` method for `passwords` defaults to `S2K.Iterated`;
+/// `S2K.Argon2` is only available for SEIPDv2 messages.
+///
+/// Note that removed recipients can still decrypt copies of the message they
+/// have already received, as the session key stays the same.
+#[pyfunction]
+#[pyo3(signature = (message, decryptor, add_recipients=vec![], remove_recipients=vec![], passwords=vec![], *, armor=true, s2k=None))]
+pub fn reencrypt(
+    message: &[u8],
+    decryptor: Decryptors,
+    add_recipients: Vec<Cert>,
+    remove_recipients: Vec<Cert>,
+    passwords: Vec<String>,
+    armor: bool,
+    s2k: Option<types::S2K>,
+) -> PyResult<Cow<'static, [u8]>> {
+    let changes = Changes {
+        add_recipients,
+        remove_recipients,
+        passwords,
+        s2k,
+    };
+    let mut sink = vec![];
+    reencrypt_into(
+        PacketParser::from_bytes(message)?,
+        &mut decryptor.into_decryptor()?,
+        &changes,
+        &mut sink,
+        armor,
+    )?;
+    Ok(sink.into())
+}
+
+/// Change the recipients of an encrypted message in a file, writing the result to another file.
+///
+/// The encrypted data is streamed from `input` to `output`; see `reencrypt` for
+/// the other parameters.
+#[pyfunction]
+#[pyo3(signature = (input, output, decryptor, add_recipients=vec![], remove_recipients=vec![], passwords=vec![], *, armor=true, s2k=None))]
+pub fn reencrypt_file(
+    input: PathBuf,
+    output: PathBuf,
+    decryptor: Decryptors,
+    add_recipients: Vec<Cert>,
+    remove_recipients: Vec<Cert>,
+    passwords: Vec<String>,
+    armor: bool,
+    s2k: Option<types::S2K>,
+) -> PyResult<()> {
+    let changes = Changes {
+        add_recipients,
+        remove_recipients,
+        passwords,
+        s2k,
+    };
+    let ppr = PacketParser::from_file(&input).context("Failed to open input file")?;
+    let mut sink = File::create(&output).context("Failed to create output file")?;
+    reencrypt_into(
+        ppr,
+        &mut decryptor.into_decryptor()?,
+        &changes,
+        &mut sink,
+        armor,
+    )?;
+    sink.flush()?;
+    Ok(())
+}
@@ -1,3 +1,8 @@
+#![expect(
+    clippy::too_many_arguments,
+    reason = "signing options are keyword arguments in Python"
+)]
+
 use std::borrow::Cow;
 use std::io::Write;
 use std::path::PathBuf;
@@ -6,9 +11,11 @@ use anyhow::Context;
 use pyo3::prelude::*;
 use sequoia_openpgp::armor;
 use sequoia_openpgp::serialize::stream::Armorer;
-use sequoia_openpgp::serialize::stream::{LiteralWriter, Message};
+use sequoia_openpgp::serialize::stream::Message;
 
+use crate::LiteralMetadata;
 use crate::signer::PySigner;
+use crate::types::{CompressionAlgorithm, DataFormat};
 
 /// The mode of signature to produce.
 #[pyclass(eq, eq_int)]
@@ -26,15 +33,33 @@ pub enum SignatureMode {
 ///
 /// The `mode` controls whether the signature is inline (the default), detached, or cleartext.
 /// Set `armor=False` to produce binary output instead of ASCII-armored.
+/// Set `compression` to compress inline-signed messages. The `filename`,
+/// `date` and `format` are stored with the content of inline-signed messages.
+/// Each of the additional `signers` adds another signature alongside the one by `signer`.
 #[pyfunction]
-#[pyo3(signature = (signer, bytes, *, mode=&SignatureMode::Inline, armor=true))]
+#[pyo3(signature = (signer, bytes, *, mode=&SignatureMode::Inline, armor=true, compression=None, filename=None, date=None, format=None, signers=vec![]))]
 pub fn sign(
     signer: PySigner,
     bytes: &[u8],
     mode: &SignatureMode,
     armor: bool,
+    compression: Option<CompressionAlgorithm>,
+    filename: Option<String>,
+    date: Option<chrono::DateTime<chrono::Utc>>,
+    format: Option<DataFormat>,
+    signers: Vec<PySigner>,
 ) -> PyResult<Cow<'static, [u8]>> {
-    use sequoia_openpgp::serialize::stream::Signer;
+    let literal = LiteralMetadata {
+        filename,
+        date,
+        format,
+    };
+    if (compression.is_some() || !literal.is_empty()) && mode != &SignatureMode::Inline {
+        return Err(anyhow::anyhow!(
+            "Compression and literal data metadata are only supported for inline signatures"
+        )
+        .into());
+    }
 
     let mut sink = vec![];
     {
@@ -46,9 +71,10 @@ pub fn sign(
         } else {
             message
         };
-        let message = Signer::new(message, signer)?;
+        let message = crate::compress(message, compression)?;
+        let message = crate::signer::signer(message, std::iter::once(signer).chain(signers))?;
         let mut message = if mode == &SignatureMode::Inline {
-            LiteralWriter::new(message.build()?).build()?
+            literal.writer(message.build()?)?
         } else if mode == &SignatureMode::Detached {
             message.detached().build()?
         } else {
@@ -65,16 +91,34 @@ pub fn sign(
 ///
 /// The `mode` controls whether the signature is inline (the default), detached, or cleartext.
 /// Set `armor=False` to produce binary output instead of ASCII-armored.
+/// Set `compression` to compress inline-signed messages. The `filename`,
+/// `date` and `format` are stored with the content of inline-signed messages.
+/// Each of the additional `signers` adds another signature alongside the one by `signer`.
 #[pyfunction]
-#[pyo3(signature = (signer, input, output, *, mode=&SignatureMode::Inline, armor=true))]
+#[pyo3(signature = (signer, input, output, *, mode=&SignatureMode::Inline, armor=true, compression=None, filename=None, date=None, format=None, signers=vec![]))]
 pub fn sign_file(
     signer: PySigner,
     input: PathBuf,
     output: PathBuf,
     mode: &SignatureMode,
     armor: bool,
+    compression: Option<CompressionAlgorithm>,
+    filename: Option<String>,
+    date: Option<chrono::DateTime<chrono::Utc>>,
+    format: Option<DataFormat>,
+    signers: Vec<PySigner>,
 ) -> PyResult<()> {
-    use sequoia_openpgp::serialize::stream::Signer;
+    let literal = LiteralMetadata {
+        filename,
+        date,
+        format,
+    };
+    if (compression.is_some() || !literal.is_empty()) && mode != &SignatureMode::Inline {
+        return Err(anyhow::anyhow!(
+            "Compression and literal data metadata are only supported for inline signatures"
+        )
+        .into());
+    }
 
     let mut sink = std::fs::File::create(&output).context("Failed to create output file")?;
     {
@@ -86,9 +130,10 @@ pub fn sign_file(
         } else {
             message
         };
-        let message = Signer::new(message, signer)?;
+        let message = crate::compress(message, compression)?;
+        let message = crate::signer::signer(message, std::iter::once(signer).chain(signers))?;
         let mut message = if mode == &SignatureMode::Inline {
-            LiteralWriter::new(message.build()?).build()?
+            literal.writer(message.build()?)?
         } else if mode == &SignatureMode::Detached {
             message.detached().build()?
         } else {
@@ -1,6 +1,8 @@
 use std::sync::{Arc, Mutex};
 
+use anyhow::Context;
 use pyo3::prelude::*;
+use sequoia_openpgp::serialize::stream::{Message, Signer};
 use sequoia_openpgp::{crypto, packet, types};
 
 /// A handle to a signing key, used for creating signatures, certifications, and revocations.
@@ -23,6 +25,20 @@ impl PySigner {
     }
 }
 
+/// Creates a signing writer that adds a signature by each of the `signers`.
+pub(crate) fn signer<'a>(
+    message: Message<'a>,
+    signers: impl IntoIterator<Item = PySigner>,
+) -> anyhow::Result<Signer<'a>> {
+    let mut signers = signers.into_iter();
+    let first = signers.next().context("At least one signer is required")?;
+    let mut signer = Signer::new(message, first)?;
+    for other in signers {
+        signer = signer.add_signer(other)?;
+    }
+    Ok(signer)
+}
+
 impl crypto::Signer for PySigner {
     fn public(&self) -> &packet::Key<packet::key::PublicParts, packet::key::UnspecifiedRole> {
         &self.public
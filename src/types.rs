@@ -5,12 +5,14 @@
 
 use anyhow::anyhow;
 use pyo3::prelude::*;
+use sequoia_openpgp::crypto::S2K as SqS2K;
 use sequoia_openpgp::packet::Tag as SqTag;
 use sequoia_openpgp::types::{
+    AEADAlgorithm as SqAEADAlgorithm, CompressionAlgorithm as SqCompressionAlgorithm,
     DataFormat as SqDataFormat, HashAlgorithm as SqHashAlgorithm, KeyFlags as SqKeyFlags,
     PublicKeyAlgorithm as SqPublicKeyAlgorithm,
     PublicKeyAlgorithmSpecification as SqPublicKeyAlgorithmSpecification,
-    SignatureType as SqSignatureType,
+    SignatureType as SqSignatureType, SymmetricAlgorithm as SqSymmetricAlgorithm,
 };
 
 /// The type of an OpenPGP signature, as defined in RFC 4880 / 9580.
@@ -80,7 +82,7 @@ impl TryFrom<SqSignatureType> for SignatureType {
 
 /// The public key algorithm used by an OpenPGP key.
 #[pyclass(eq, skip_from_py_object)]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[expect(
     non_camel_case_types,
     reason = "type names aligned with the specification"
@@ -213,7 +215,7 @@ impl TryFrom<SqHashAlgorithm> for HashAlgorithm {
 }
 
 /// The data format of a Literal Data packet.
-#[pyclass(eq, skip_from_py_object)]
+#[pyclass(eq, from_py_object)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DataFormat {
     /// Binary data
@@ -225,6 +227,16 @@ pub enum DataFormat {
     Text,
 }
 
+impl From<DataFormat> for SqDataFormat {
+    fn from(fmt: DataFormat) -> Self {
+        match fmt {
+            DataFormat::Binary => Self::Binary,
+            DataFormat::Unicode => Self::Unicode,
+            DataFormat::Text => Self::Text,
+        }
+    }
+}
+
 impl TryFrom<SqDataFormat> for DataFormat {
     type Error = anyhow::Error;
 
@@ -317,9 +329,163 @@ impl TryFrom<SqTag> for Tag {
     }
 }
 
+/// The symmetric cipher used to encrypt message data.
+#[pyclass(eq, from_py_object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymmetricAlgorithm {
+    /// IDEA (legacy)
+    IDEA,
+    /// TripleDES (legacy)
+    TripleDES,
+    /// CAST5 (legacy)
+    CAST5,
+    /// Blowfish (legacy)
+    Blowfish,
+    /// AES with 128-bit key
+    AES128,
+    /// AES with 192-bit key
+    AES192,
+    /// AES with 256-bit key
+    AES256,
+    /// Twofish with 256-bit key
+    Twofish,
+    /// Camellia with 128-bit key
+    Camellia128,
+    /// Camellia with 192-bit key
+    Camellia192,
+    /// Camellia with 256-bit key
+    Camellia256,
+}
+
+impl From<SymmetricAlgorithm> for SqSymmetricAlgorithm {
+    fn from(algo: SymmetricAlgorithm) -> Self {
+        match algo {
+            SymmetricAlgorithm::IDEA => Self::IDEA,
+            SymmetricAlgorithm::TripleDES => Self::TripleDES,
+            SymmetricAlgorithm::CAST5 => Self::CAST5,
+            SymmetricAlgorithm::Blowfish => Self::Blowfish,
+            SymmetricAlgorithm::AES128 => Self::AES128,
+            SymmetricAlgorithm::AES192 => Self::AES192,
+            SymmetricAlgorithm::AES256 => Self::AES256,
+            SymmetricAlgorithm::Twofish => Self::Twofish,
+            SymmetricAlgorithm::Camellia128 => Self::Camellia128,
+            SymmetricAlgorithm::Camellia192 => Self::Camellia192,
+            SymmetricAlgorithm::Camellia256 => Self::Camellia256,
+        }
+    }
+}
+
+impl TryFrom<SqSymmetricAlgorithm> for SymmetricAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(algo: SqSymmetricAlgorithm) -> Result<Self, Self::Error> {
+        match algo {
+            SqSymmetricAlgorithm::IDEA => Ok(Self::IDEA),
+            SqSymmetricAlgorithm::TripleDES => Ok(Self::TripleDES),
+            SqSymmetricAlgorithm::CAST5 => Ok(Self::CAST5),
+            SqSymmetricAlgorithm::Blowfish => Ok(Self::Blowfish),
+            SqSymmetricAlgorithm::AES128 => Ok(Self::AES128),
+            SqSymmetricAlgorithm::AES192 => Ok(Self::AES192),
+            SqSymmetricAlgorithm::AES256 => Ok(Self::AES256),
+            SqSymmetricAlgorithm::Twofish => Ok(Self::Twofish),
+            SqSymmetricAlgorithm::Camellia128 => Ok(Self::Camellia128),
+            SqSymmetricAlgorithm::Camellia192 => Ok(Self::Camellia192),
+            SqSymmetricAlgorithm::Camellia256 => Ok(Self::Camellia256),
+            SqSymmetricAlgorithm::Private(u) => Err(anyhow!("Private symmetric algorithm: {u}")),
+            SqSymmetricAlgorithm::Unknown(u) => Err(anyhow!("Unknown symmetric algorithm: {u}")),
+            _ => Err(anyhow!(
+                "Unrecognized symmetric algorithm: {}",
+                u8::from(algo)
+            )),
+        }
+    }
+}
+
+/// The AEAD mode used to protect message data (SEIPDv2, RFC 9580).
+#[pyclass(eq, from_py_object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AEADAlgorithm {
+    /// EAX mode
+    EAX,
+    /// OCB mode, mandatory to implement
+    OCB,
+    /// GCM mode
+    GCM,
+}
+
+impl From<AEADAlgorithm> for SqAEADAlgorithm {
+    fn from(algo: AEADAlgorithm) -> Self {
+        match algo {
+            AEADAlgorithm::EAX => Self::EAX,
+            AEADAlgorithm::OCB => Self::OCB,
+            AEADAlgorithm::GCM => Self::GCM,
+        }
+    }
+}
+
+impl TryFrom<SqAEADAlgorithm> for AEADAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(algo: SqAEADAlgorithm) -> Result<Self, Self::Error> {
+        match algo {
+            SqAEADAlgorithm::EAX => Ok(Self::EAX),
+            SqAEADAlgorithm::OCB => Ok(Self::OCB),
+            SqAEADAlgorithm::GCM => Ok(Self::GCM),
+            SqAEADAlgorithm::Private(u) => Err(anyhow!("Private AEAD algorithm: {u}")),
+            SqAEADAlgorithm::Unknown(u) => Err(anyhow!("Unknown AEAD algorithm: {u}")),
+            _ => Err(anyhow!("Unrecognized AEAD algorithm: {}", u8::from(algo))),
+        }
+    }
+}
+
+/// The compression algorithm used for message data.
+#[pyclass(eq, from_py_object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// DEFLATE (RFC 1951)
+    Zip,
+    /// DEFLATE with ZLIB framing (RFC 1950)
+    Zlib,
+    /// BZip2, requires the `compression-bzip2` cargo feature
+    BZip2,
+}
+
+impl From<CompressionAlgorithm> for SqCompressionAlgorithm {
+    fn from(algo: CompressionAlgorithm) -> Self {
+        match algo {
+            CompressionAlgorithm::Zip => Self::Zip,
+            CompressionAlgorithm::Zlib => Self::Zlib,
+            CompressionAlgorithm::BZip2 => Self::BZip2,
+        }
+    }
+}
+
+impl TryFrom<SqCompressionAlgorithm> for CompressionAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(algo: SqCompressionAlgorithm) -> Result<Self, Self::Error> {
+        match algo {
+            SqCompressionAlgorithm::Zip => Ok(Self::Zip),
+            SqCompressionAlgorithm::Zlib => Ok(Self::Zlib),
+            SqCompressionAlgorithm::BZip2 => Ok(Self::BZip2),
+            SqCompressionAlgorithm::Uncompressed => Err(anyhow!("Data is not compressed")),
+            SqCompressionAlgorithm::Private(u) => {
+                Err(anyhow!("Private compression algorithm: {u}"))
+            }
+            SqCompressionAlgorithm::Unknown(u) => {
+                Err(anyhow!("Unknown compression algorithm: {u}"))
+            }
+            _ => Err(anyhow!(
+                "Unrecognized compression algorithm: {}",
+                u8::from(algo)
+            )),
+        }
+    }
+}
+
 /// The type of ASCII armor to use when wrapping OpenPGP data.
 #[pyclass(eq, from_py_object)]
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ArmorKind {
     /// `PGP PUBLIC KEY BLOCK`
     PublicKey,
@@ -342,6 +508,73 @@ impl From<ArmorKind> for sequoia_openpgp::armor::Kind {
     }
 }
 
+impl TryFrom<sequoia_openpgp::armor::Kind> for ArmorKind {
+    type Error = anyhow::Error;
+
+    fn try_from(kind: sequoia_openpgp::armor::Kind) -> Result<Self, Self::Error> {
+        use sequoia_openpgp::armor::Kind;
+        match kind {
+            Kind::PublicKey => Ok(Self::PublicKey),
+            Kind::SecretKey => Ok(Self::SecretKey),
+            Kind::Message => Ok(Self::Message),
+            Kind::Signature => Ok(Self::Signature),
+            _ => Err(anyhow!("Unsupported armor kind: {kind:?}")),
+        }
+    }
+}
+
+/// The string-to-key method deriving a symmetric key from a password.
+#[pyclass(eq, from_py_object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum S2K {
+    /// Argon2 memory-hard key derivation (RFC 9580)
+    Argon2,
+    /// Iterated and salted hashing
+    Iterated,
+    /// Salted hashing, deprecated
+    Salted,
+    /// Plain hashing, deprecated
+    Simple,
+}
+
+impl TryFrom<S2K> for SqS2K {
+    type Error = anyhow::Error;
+
+    fn try_from(s2k: S2K) -> Result<Self, Self::Error> {
+        match s2k {
+            S2K::Argon2 => {
+                let mut salt = [0; 16];
+                sequoia_openpgp::crypto::random(&mut salt)?;
+                // The second recommended parameter set of RFC 9580, using 64 MiB of memory.
+                Ok(Self::Argon2 {
+                    salt,
+                    t: 3,
+                    p: 4,
+                    m: 16,
+                })
+            }
+            S2K::Iterated => Ok(Self::default()),
+            S2K::Salted | S2K::Simple => Err(anyhow!("The {s2k:?} S2K method is deprecated")),
+        }
+    }
+}
+
+impl TryFrom<&SqS2K> for S2K {
+    type Error = anyhow::Error;
+
+    fn try_from(s2k: &SqS2K) -> Result<Self, Self::Error> {
+        match s2k {
+            SqS2K::Argon2 { .. } => Ok(Self::Argon2),
+            SqS2K::Iterated { .. } => Ok(Self::Iterated),
+            SqS2K::Salted { .. } => Ok(Self::Salted),
+            SqS2K::Simple { .. } => Ok(Self::Simple),
+            SqS2K::Private { tag, .. } => Err(anyhow!("Private S2K method: {tag}")),
+            SqS2K::Unknown { tag, .. } => Err(anyhow!("Unknown S2K method: {tag}")),
+            _ => Err(anyhow!("Unrecognized S2K method: {s2k:?}")),
+        }
+    }
+}
+
 /// The signing algorithm to use when generating keys.
 ///
 /// Used with `Cert.generate(signing_algorithm=...)` to override the signing
@@ -1,13 +1,16 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
 
 use anyhow::anyhow;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use sequoia_openpgp::KeyHandle;
-use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::packet::Packet;
+use sequoia_openpgp::parse::{PacketParser, Parse};
+use sequoia_openpgp::{Fingerprint, KeyHandle};
 use sequoia_openpgp::{cert, parse::stream::*, policy::StandardPolicy};
 
 use crate::signature::Sig;
-use crate::{Decrypted, ValidSig};
+use crate::{Decrypted, LayerKind, LiteralMetadata, PyMessageLayer, ValidSig};
 
 enum SignedData<'a> {
     File(PathBuf),
@@ -23,18 +26,137 @@ impl From<SignedData<'_>> for Option<Vec<u8>> {
     }
 }
 
+/// Requirements a message's signatures have to meet.
+///
+/// Valid signatures by at least `min_valid` distinct certificates are
+/// required. If `signers` is given, only signatures by these certificate or
+/// signing key fingerprints count; invalid fingerprints raise a `ValueError`.
+/// Set `allow_unsigned=True` to also accept messages without any signatures.
+#[pyclass(from_py_object)]
+#[derive(Clone, Debug)]
+pub struct SignaturePolicy {
+    signers: Vec<String>,
+    min_valid: usize,
+    allow_unsigned: bool,
+}
+
+impl Default for SignaturePolicy {
+    fn default() -> Self {
+        Self {
+            signers: vec![],
+            min_valid: 1,
+            allow_unsigned: false,
+        }
+    }
+}
+
+impl SignaturePolicy {
+    fn accepts(&self, sig: &ValidSig) -> bool {
+        self.signers.is_empty()
+            || self
+                .signers
+                .iter()
+                .any(|signer| *signer == sig.certificate || *signer == sig.signing_key)
+    }
+
+    fn check(&self, layers: &[PyMessageLayer], valid_sigs: &[ValidSig]) -> anyhow::Result<()> {
+        let signed = layers
+            .iter()
+            .any(|layer| layer.kind == LayerKind::SignatureGroup);
+        if !signed && self.allow_unsigned {
+            return Ok(());
+        }
+        let accepted = valid_sigs
+            .iter()
+            .filter(|sig| self.accepts(sig))
+            .map(|sig| &sig.certificate)
+            .collect::<HashSet<_>>()
+            .len();
+        if accepted >= self.min_valid {
+            Ok(())
+        } else if valid_sigs.is_empty() {
+            Err(anyhow!(
+                "Signature verification failed: no valid signatures found."
+            ))
+        } else {
+            Err(anyhow!(
+                "Signature verification failed: valid signatures by {accepted} of {} required certificates found.",
+                self.min_valid
+            ))
+        }
+    }
+}
+
+#[pymethods]
+impl SignaturePolicy {
+    /// Describe the signatures a message must carry.
+    #[new]
+    #[pyo3(signature = (*, signers=vec![], min_valid=1, allow_unsigned=false))]
+    pub fn new(signers: Vec<String>, min_valid: usize, allow_unsigned: bool) -> PyResult<Self> {
+        if min_valid == 0 {
+            return Err(PyValueError::new_err("min_valid must be at least 1"));
+        }
+        let signers = signers
+            .iter()
+            .map(|signer| match signer.parse::<Fingerprint>() {
+                Ok(fingerprint) if !matches!(fingerprint, Fingerprint::Unknown { .. }) => {
+                    Ok(format!("{fingerprint:x}"))
+                }
+                _ => Err(PyValueError::new_err(format!(
+                    "Invalid fingerprint: {signer}"
+                ))),
+            })
+            .collect::<PyResult<_>>()?;
+        Ok(Self {
+            signers,
+            min_valid,
+            allow_unsigned,
+        })
+    }
+
+    /// The fingerprints of the accepted signers; empty to accept any signer.
+    #[getter]
+    pub fn signers(&self) -> Vec<String> {
+        self.signers.clone()
+    }
+
+    /// The number of distinct certificates whose valid signatures are required.
+    #[getter]
+    pub fn min_valid(&self) -> usize {
+        self.min_valid
+    }
+
+    /// Whether messages without any signatures are accepted.
+    #[getter]
+    pub fn allow_unsigned(&self) -> bool {
+        self.allow_unsigned
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "<SignaturePolicy signers={} min_valid={} allow_unsigned={}>",
+            self.signers.len(),
+            self.min_valid,
+            self.allow_unsigned
+        )
+    }
+}
+
 /// Verify an OpenPGP signature.
 ///
 /// Provide either `bytes` or `file` as the signed data source. The `store` callback
 /// is called with a list of key ID strings and must return a list of `Cert` objects.
 /// For detached signature verification, pass a `Sig` object as `signature`.
+/// At least one valid signature is required unless `require_signatures` sets
+/// another `SignaturePolicy`.
 #[pyfunction]
-#[pyo3(signature = (bytes=None, store=None, file=None, signature=None))]
+#[pyo3(signature = (bytes=None, store=None, file=None, signature=None, *, require_signatures=None))]
 pub fn verify(
     bytes: Option<&[u8]>,
     store: Option<Py<PyAny>>,
     file: Option<PathBuf>,
     signature: Option<&Sig>,
+    require_signatures: Option<SignaturePolicy>,
 ) -> PyResult<Decrypted> {
     let Some(store) = store else {
         return Err(anyhow!("Store parameter is required").into());
@@ -50,7 +172,10 @@ pub fn verify(
         return Err(anyhow!("Either `bytes` or `file` parameter should be given.").into());
     };
 
-    let helper = PyVerifier::from_callback(store);
+    let mut helper = PyVerifier::from_callback(store);
+    if let Some(require_signatures) = require_signatures {
+        helper.set_signature_policy(require_signatures);
+    }
 
     let policy = &StandardPolicy::new();
 
@@ -70,6 +195,10 @@ pub fn verify(
         Ok(Decrypted {
             content: signed_data.into(),
             valid_sigs: helper.valid_sigs,
+            literal: LiteralMetadata::default(),
+            session_key: None,
+            decrypted_with: None,
+            layers: helper.layers,
         })
     } else {
         // inline signature verification
@@ -87,6 +216,10 @@ pub fn verify(
         Ok(Decrypted {
             content: Some(sink),
             valid_sigs: helper.valid_sigs,
+            literal: helper.literal,
+            session_key: None,
+            decrypted_with: None,
+            layers: helper.layers,
         })
     }
 }
@@ -95,6 +228,9 @@ pub fn verify(
 pub struct PyVerifier {
     store: Py<PyAny>,
     valid_sigs: Vec<ValidSig>,
+    literal: LiteralMetadata,
+    layers: Vec<PyMessageLayer>,
+    signature_policy: SignaturePolicy,
 }
 
 impl PyVerifier {
@@ -102,15 +238,51 @@ impl PyVerifier {
         Self {
             store,
             valid_sigs: vec![],
+            literal: LiteralMetadata::default(),
+            layers: vec![],
+            signature_policy: SignaturePolicy::default(),
         }
     }
 
+    pub fn set_signature_policy(&mut self, signature_policy: SignaturePolicy) {
+        self.signature_policy = signature_policy;
+    }
+
     pub fn valid_sigs(self) -> Vec<ValidSig> {
         self.valid_sigs
     }
+
+    /// Checks the signatures of the message layers, outermost first.
+    ///
+    /// The layers may be nested in any way the message grammar allows, e.g.
+    /// signed messages that are encrypted and signed again. Only signature
+    /// groups inside the innermost encryption layer sign the plaintext, so
+    /// signatures outside of it are reported in the layers but count neither
+    /// as valid signatures nor towards the signature policy.
+    pub(crate) fn check_layers(&mut self, layers: Vec<PyMessageLayer>) -> anyhow::Result<()> {
+        let plaintext = layers
+            .iter()
+            .rposition(|layer| layer.kind == LayerKind::Encryption)
+            .map_or(0, |encryption| encryption + 1);
+        self.valid_sigs = layers[plaintext..]
+            .iter()
+            .flat_map(|layer| layer.valid_sigs.iter().cloned())
+            .collect();
+        self.layers = layers;
+
+        self.signature_policy
+            .check(&self.layers[plaintext..], &self.valid_sigs)
+    }
 }
 
 impl VerificationHelper for PyVerifier {
+    fn inspect(&mut self, pp: &PacketParser) -> sequoia_openpgp::Result<()> {
+        if let Packet::Literal(literal) = &pp.packet {
+            self.literal = LiteralMetadata::from_packet(literal);
+        }
+        Ok(())
+    }
+
     fn get_certs(&mut self, ids: &[KeyHandle]) -> sequoia_openpgp::Result<Vec<cert::Cert>> {
         let mut certs = vec![];
         let result: Vec<crate::cert::Cert> = Python::attach(|py| {
@@ -127,28 +299,6 @@ impl VerificationHelper for PyVerifier {
     }
 
     fn check(&mut self, structure: MessageStructure) -> sequoia_openpgp::Result<()> {
-        let mut valid_sigs = vec![];
-        for (i, layer) in structure.into_iter().enumerate() {
-            match layer {
-                MessageLayer::Encryption { .. } if i == 0 => (),
-                MessageLayer::Compression { .. } if (0..1).contains(&i) => (),
-                MessageLayer::SignatureGroup { results } if (0..2).contains(&i) => {
-                    for result in results.into_iter().flatten() {
-                        valid_sigs.push(result.into());
-                    }
-                }
-                _ => return Err(anyhow::anyhow!("Unexpected message structure")),
-            }
-        }
-
-        self.valid_sigs = valid_sigs;
-
-        if !self.valid_sigs.is_empty() {
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!(
-                "Signature verification failed: no valid signatures found."
-            ))
-        }
+        self.check_layers(structure.into_iter().map(PyMessageLayer::from).collect())
     }
 }
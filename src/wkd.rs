@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use pyo3::prelude::*;
+use sequoia_openpgp::cert::{self, CertParser};
+use sequoia_openpgp::parse::Parse;
+use sequoia_openpgp::serialize::Marshal;
+use sequoia_openpgp::types::HashAlgorithm;
+
+use crate::cert::{Cert, split_email};
+use crate::http::{percent_encode, request};
+
+/// Alphabet of the z-base-32 encoding used for WKD local part hashes.
+const ZBASE32_ALPHABET: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+fn zbase32(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ZBASE32_ALPHABET[usize::from((buffer >> bits) & 0x1f)] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ZBASE32_ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)] as char);
+    }
+    encoded
+}
+
+fn has_domain(email: &str, domain: &str) -> bool {
+    split_email(email).is_ok_and(|(_, d)| d == domain)
+}
+
+fn hash_local_part(local: &str) -> anyhow::Result<String> {
+    let digest = crate::digest(HashAlgorithm::SHA1, local.to_ascii_lowercase().as_bytes())?;
+    Ok(zbase32(&digest))
+}
+
+/// Compute the WKD hash of the local part of an email address.
+///
+/// The local part is lowercased, hashed with SHA-1 and encoded using z-base-32.
+#[pyfunction]
+pub fn local_part_hash(email: &str) -> PyResult<String> {
+    let (local, _) = split_email(email)?;
+    Ok(hash_local_part(local)?)
+}
+
+/// The URL at which the certificate for `email` is published using the advanced method.
+#[pyfunction]
+pub fn advanced_url(email: &str) -> PyResult<String> {
+    let (local, domain) = split_email(email)?;
+    Ok(format!(
+        "https://openpgpkey.{domain}/.well-known/openpgpkey/{domain}/hu/{}?l={}",
+        hash_local_part(local)?,
+        percent_encode(local)
+    ))
+}
+
+/// The URL at which the certificate for `email` is published using the direct method.
+#[pyfunction]
+pub fn direct_url(email: &str) -> PyResult<String> {
+    let (local, domain) = split_email(email)?;
+    Ok(format!(
+        "https://{domain}/.well-known/openpgpkey/hu/{}?l={}",
+        hash_local_part(local)?,
+        percent_encode(local)
+    ))
+}
+
+/// Generate a Web Key Directory for `domain` under the `path` directory.
+///
+/// Creates the `.well-known/openpgpkey` tree with an empty `policy` file and
+/// one `hu/` entry per email address. Only User IDs belonging to `domain`
+/// are published, certificates without such User IDs are skipped.
+/// Set `advanced=False` to generate the layout for the direct method.
+#[pyfunction]
+#[pyo3(signature = (path, domain, certs, *, advanced=true))]
+pub fn generate(
+    path: PathBuf,
+    domain: &str,
+    certs: Vec<PyRef<Cert>>,
+    advanced: bool,
+) -> PyResult<()> {
+    let domain = domain.to_lowercase();
+
+    let mut entries: BTreeMap<String, Vec<cert::Cert>> = BTreeMap::new();
+    for cert in certs.iter() {
+        let local_parts = cert
+            .emails()
+            .iter()
+            .filter_map(|email| match split_email(email) {
+                Ok((local, d)) if d == domain => Some(local.to_ascii_lowercase()),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        if local_parts.is_empty() {
+            continue;
+        }
+
+        let filtered = cert.cert().clone().retain_userids(|ua| {
+            ua.userid()
+                .email()
+                .ok()
+                .flatten()
+                .is_some_and(|email| has_domain(email, &domain))
+        });
+        for local in local_parts {
+            entries.entry(local).or_default().push(filtered.clone());
+        }
+    }
+
+    let mut dir = path.join(".well-known").join("openpgpkey");
+    if advanced {
+        dir = dir.join(&domain);
+    }
+    let hu = dir.join("hu");
+    std::fs::create_dir_all(&hu).context("Failed to create WKD directory")?;
+    File::create(dir.join("policy")).context("Failed to create policy file")?;
+
+    for (local, certs) in entries {
+        let mut file = File::create(hu.join(hash_local_part(&local)?))
+            .context("Failed to create WKD entry")?;
+        for cert in certs {
+            cert.export(&mut file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a WKD response for `email`.
+///
+/// Returns only the certificates that carry a User ID with the requested
+/// email address, as required by the WKD specification.
+#[pyfunction]
+pub fn parse_response(bytes: &[u8], email: &str) -> PyResult<Vec<Cert>> {
+    let email = email.to_lowercase();
+    let mut certs = vec![];
+    for cert in CertParser::from_bytes(bytes)? {
+        let cert = Cert::from(cert?);
+        if cert.emails().iter().any(|e| e.to_lowercase() == email) {
+            certs.push(cert);
+        }
+    }
+    Ok(certs)
+}
+
+/// Whether `err` means that the domain does not serve the advanced method:
+/// a 404 response or a host name that does not resolve.
+fn is_lookup_failure(py: Python<'_>, err: &PyErr) -> PyResult<bool> {
+    let errors = py.import("urllib.error")?;
+    let gaierror = py.import("socket")?.getattr("gaierror")?;
+    let value = err.value(py);
+    if value.is_instance(&errors.getattr("HTTPError")?)? {
+        Ok(value.getattr("code")?.extract::<u16>()? == 404)
+    } else if value.is_instance(&errors.getattr("URLError")?)? {
+        value.getattr("reason")?.is_instance(&gaierror)
+    } else {
+        value.is_instance(&gaierror)
+    }
+}
+
+/// Look up the certificates for `email` in its domain's Web Key Directory.
+///
+/// The advanced method is tried first, falling back to the direct method if
+/// the advanced URL is not found (HTTP 404) or its host does not resolve.
+/// The optional `transport` callable is called with the URL and must return
+/// the response body as bytes; by default `urllib` is used. Transports signal
+/// these lookup failures by raising `urllib.error.HTTPError` or `socket.gaierror`.
+#[pyfunction]
+#[pyo3(signature = (email, transport=None))]
+pub fn fetch(py: Python<'_>, email: &str, transport: Option<Py<PyAny>>) -> PyResult<Vec<Cert>> {
+    let response = match request(py, &transport, &advanced_url(email)?, None) {
+        Ok(response) => response,
+        Err(err) if is_lookup_failure(py, &err)? => {
+            request(py, &transport, &direct_url(email)?, None)?
+        }
+        Err(err) => return Err(err),
+    };
+    parse_response(&response, email)
+}
@@ -1,30 +1,58 @@
+import io
+import json
 import os
+import socket
 import tempfile
-from datetime import datetime
+import threading
+import time
+import urllib.error
+from datetime import datetime, timezone
+from http.server import BaseHTTPRequestHandler, HTTPServer
+from urllib.parse import parse_qs, unquote, urlparse
 
 import pytest
 
 from pysequoia import (
+    AEADAlgorithm,
     ArmorKind,
     Cert,
     CipherSuite,
+    CompressionAlgorithm,
     EncryptionAlgorithm,
+    EncryptionReport,
+    Encryptor,
+    Keyring,
+    KeyServer,
+    KeyServerProtocol,
     Notation,
+    Padding,
     Profile,
+    Recipient,
+    S2K,
     Sig,
     SignatureMode,
+    SignaturePolicy,
     SigningAlgorithm,
+    SymmetricAlgorithm,
     Tsk,
     armor,
+    autocrypt,
+    dane,
     decrypt,
     decrypt_file,
     encrypt,
     encrypt_file,
+    inspect_message,
+    open_decrypt,
+    open_verify,
+    reencrypt,
+    reencrypt_file,
     sign,
     sign_file,
     verify,
+    wkd,
 )
-from pysequoia.packet import PacketPile, PublicKeyAlgorithm, Tag
+from pysequoia.packet import DataFormat, PacketPile, PublicKeyAlgorithm, Tag
 
 FIXTURES = os.path.join(os.path.dirname(__file__), "fixtures")
 
@@ -954,3 +982,1881 @@ class TestErrorCases:
 
         with pytest.raises(Exception):
             verify(store=store)
+
+
+class TestWkd:
+    def test_local_part_hash(self):
+        assert wkd.local_part_hash("Joe.Doe@Example.ORG") == (
+            "iy9q119eutrkn8s1mk4r39qejnbu3n5q"
+        )
+
+    def test_urls(self):
+        assert wkd.advanced_url("Joe.Doe@Example.ORG") == (
+            "https://openpgpkey.example.org/.well-known/openpgpkey/example.org"
+            "/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe"
+        )
+        assert wkd.direct_url("Joe.Doe@Example.ORG") == (
+            "https://example.org/.well-known/openpgpkey"
+            "/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe"
+        )
+
+    def test_invalid_email(self):
+        with pytest.raises(Exception):
+            wkd.local_part_hash("not an email")
+
+    def test_generate_and_fetch(self, tmp_path):
+        alice = Tsk.generate(
+            user_ids=["Alice <alice@example.org>", "Alice <alice@private.invalid>"]
+        ).extract_certificate()
+        bob = Tsk.generate("Bob <bob@other.invalid>").extract_certificate()
+
+        wkd.generate(tmp_path, "example.org", [alice, bob])
+
+        base = tmp_path / ".well-known" / "openpgpkey" / "example.org"
+        assert (base / "policy").exists()
+        entries = list((base / "hu").iterdir())
+        assert [e.name for e in entries] == [wkd.local_part_hash("alice@example.org")]
+
+        published = Cert.from_bytes(entries[0].read_bytes())
+        assert [str(u) for u in published.user_ids] == ["Alice <alice@example.org>"]
+
+        requested = []
+
+        def transport(url):
+            requested.append(url)
+            path = url.split("://", 1)[1].split("/", 1)[1].split("?")[0]
+            return (tmp_path / path).read_bytes()
+
+        certs = wkd.fetch("alice@example.org", transport=transport)
+        assert [c.fingerprint for c in certs] == [alice.fingerprint]
+        assert requested == [wkd.advanced_url("alice@example.org")]
+
+    def test_fetch_falls_back_to_direct(self, tmp_path):
+        alice = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        wkd.generate(tmp_path, "example.org", [alice], advanced=False)
+
+        def transport(url):
+            if url.startswith("https://openpgpkey."):
+                raise socket.gaierror("no such host")
+            path = url.split("://", 1)[1].split("/", 1)[1].split("?")[0]
+            return (tmp_path / path).read_bytes()
+
+        certs = wkd.fetch("alice@example.org", transport=transport)
+        assert [c.fingerprint for c in certs] == [alice.fingerprint]
+
+    def test_fetch_falls_back_on_not_found(self):
+        alice = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+
+        def transport(url):
+            if url.startswith("https://openpgpkey."):
+                raise urllib.error.HTTPError(url, 404, "Not Found", {}, None)
+            return bytes(alice)
+
+        certs = wkd.fetch("alice@example.org", transport=transport)
+        assert [c.fingerprint for c in certs] == [alice.fingerprint]
+
+    def test_fetch_does_not_fall_back_on_other_errors(self):
+        requested = []
+
+        def transport(url):
+            requested.append(url)
+            raise urllib.error.HTTPError(url, 500, "Server Error", {}, None)
+
+        with pytest.raises(urllib.error.HTTPError):
+            wkd.fetch("alice@example.org", transport=transport)
+        assert requested == [wkd.advanced_url("alice@example.org")]
+
+        with pytest.raises(Exception):
+            wkd.fetch("alice@example.org", transport=lambda url: b"not a cert")
+
+    def test_parse_response_filters_by_email(self):
+        alice = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        mallory = Tsk.generate("Mallory <mallory@example.org>").extract_certificate()
+        response = bytes(alice) + bytes(mallory)
+        certs = wkd.parse_response(response, "alice@example.org")
+        assert [c.fingerprint for c in certs] == [alice.fingerprint]
+
+    def test_parse_response_skips_revoked_user_ids(self):
+        tsk = Tsk.generate("Alice <alice@example.org>")
+        cert = tsk.extract_certificate().add_user_id(
+            value="Alice <old@example.org>", certifier=tsk.certifier()
+        )
+        old = next(uid for uid in cert.user_ids if "old@" in str(uid))
+        revocation = cert.revoke_user_id(user_id=old, certifier=tsk.certifier())
+        response = bytes(cert) + bytes(revocation)
+        assert wkd.parse_response(response, "old@example.org") == []
+        assert len(wkd.parse_response(response, "alice@example.org")) == 1
+
+
+@pytest.fixture
+def keyserver():
+    """A local stand-in speaking both HKP and VKS, storing uploads in memory."""
+    stored = {}
+
+    class Handler(BaseHTTPRequestHandler):
+        def log_message(self, *args):
+            pass
+
+        def _find(self, query):
+            query = query.lower().removeprefix("0x")
+            for fingerprint, cert in stored.items():
+                emails = [str(u) for u in cert.user_ids]
+                if fingerprint.endswith(query) or any(query in e for e in emails):
+                    return cert
+            return None
+
+        def do_GET(self):
+            url = urlparse(self.path)
+            if url.path == "/pks/lookup":
+                cert = self._find(parse_qs(url.query)["search"][0])
+            elif url.path.startswith("/vks/v1/by-"):
+                cert = self._find(unquote(url.path.rsplit("/", 1)[1]))
+            else:
+                cert = None
+            if cert is None:
+                self.send_response(404)
+                self.end_headers()
+                return
+            self.send_response(200)
+            self.end_headers()
+            self.wfile.write(str(cert).encode("utf8"))
+
+        def do_POST(self):
+            body = self.rfile.read(int(self.headers["Content-Length"])).decode("utf8")
+            if self.path == "/pks/add":
+                keytext = parse_qs(body)["keytext"][0]
+            else:
+                keytext = json.loads(body)["keytext"]
+            cert = Cert.from_bytes(keytext.encode("utf8"))
+            stored[cert.fingerprint] = cert
+            self.send_response(200)
+            self.end_headers()
+            self.wfile.write(b"{}")
+
+    server = HTTPServer(("127.0.0.1", 0), Handler)
+    thread = threading.Thread(target=server.serve_forever, daemon=True)
+    thread.start()
+    yield f"http://127.0.0.1:{server.server_port}"
+    server.shutdown()
+
+
+class TestKeyServer:
+    @pytest.mark.parametrize("protocol", [KeyServerProtocol.HKP, KeyServerProtocol.VKS])
+    def test_send_and_get(self, keyserver, protocol):
+        tsk = Tsk.generate("Alice <alice@example.org>")
+        cert = tsk.extract_certificate()
+        server = KeyServer(keyserver, protocol)
+
+        server.send(tsk.extract_certificate())
+
+        by_fpr = server.get(cert.fingerprint)
+        assert [c.fingerprint for c in by_fpr] == [cert.fingerprint]
+        by_key_id = server.get("0x" + cert.fingerprint[-16:])
+        assert [c.fingerprint for c in by_key_id] == [cert.fingerprint]
+        by_email = server.get("alice@example.org")
+        assert [c.fingerprint for c in by_email] == [cert.fingerprint]
+
+    def test_send_strips_secrets(self, keyserver):
+        tsk = Tsk.generate("Alice <alice@example.org>")
+        server = KeyServer(keyserver, KeyServerProtocol.VKS)
+        server.send(tsk.extract_certificate())
+        fetched = server.get(tsk.extract_certificate().fingerprint)[0]
+        assert "PRIVATE KEY" not in str(fetched)
+
+    def test_missing_key(self, keyserver):
+        server = KeyServer(keyserver, KeyServerProtocol.HKP)
+        with pytest.raises(Exception):
+            server.get("alice@example.org")
+
+    def test_invalid_query(self, keyserver):
+        with pytest.raises(Exception):
+            KeyServer(keyserver).get("not a key")
+
+    def test_transport(self):
+        cert = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        requested = []
+
+        def transport(url):
+            requested.append(url)
+            return bytes(cert)
+
+        server = KeyServer("https://keys.example.org/", transport=transport)
+        assert server.url == "https://keys.example.org"
+        server.get(cert.fingerprint)
+        assert requested == [
+            "https://keys.example.org/vks/v1/by-fingerprint/"
+            + cert.fingerprint.upper()
+        ]
+
+
+class TestAutocrypt:
+    def test_header_roundtrip(self):
+        tsk = Tsk.generate(
+            user_ids=["Alice <alice@example.org>", "Alice <alice@work.invalid>"]
+        )
+        cert = tsk.extract_certificate()
+        value = autocrypt.generate_header(
+            cert, "alice@example.org", prefer_encrypt=True
+        )
+        assert value.startswith(
+            "addr=alice@example.org; prefer-encrypt=mutual; keydata="
+        )
+
+        header = autocrypt.parse_header(value)
+        assert header.addr == "alice@example.org"
+        assert header.prefer_encrypt
+        assert header.cert.fingerprint == cert.fingerprint
+        assert [str(u) for u in header.cert.user_ids] == ["Alice <alice@example.org>"]
+
+        encrypted = encrypt(b"hello", recipients=[header.cert])
+        assert decrypt(encrypted, decryptor=tsk.decryptor()).bytes == b"hello"
+
+    def test_minimized_keys(self):
+        cert = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        header = autocrypt.parse_header(
+            autocrypt.generate_header(cert, "alice@example.org")
+        )
+        assert not header.prefer_encrypt
+        keys = [
+            p
+            for p in PacketPile.from_bytes(bytes(header.cert))
+            if p.tag in (Tag.PublicKey, Tag.PublicSubkey)
+        ]
+        assert len(keys) == 3
+
+    def test_parse_folded_header(self):
+        cert = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        value = autocrypt.generate_header(cert, "alice@example.org")
+        prefix, keydata = value.split("keydata=")
+        folded = prefix + "_extra=ignored; keydata=\r\n " + "\r\n ".join(
+            keydata[i : i + 76] for i in range(0, len(keydata), 76)
+        )
+        assert autocrypt.parse_header(folded).cert.fingerprint == cert.fingerprint
+
+    def test_unknown_critical_attribute(self):
+        cert = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        value = autocrypt.generate_header(cert, "alice@example.org")
+        with pytest.raises(Exception):
+            autocrypt.parse_header("critical=yes; " + value)
+
+    def test_wrong_addr(self):
+        cert = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        with pytest.raises(Exception):
+            autocrypt.generate_header(cert, "bob@example.org")
+
+    def test_setup_message(self):
+        tsk = Tsk.generate("Alice <alice@example.org>")
+        message, passcode = autocrypt.generate_setup_message(tsk)
+        assert "Passphrase-Format: numeric9x4" in message
+        assert f"Passphrase-Begin: {passcode[:2]}" in message
+        assert len(passcode.split("-")) == 9
+        assert all(len(block) == 4 and block.isdigit() for block in passcode.split("-"))
+
+        restored = autocrypt.parse_setup_message(message, passcode)
+        assert (
+            restored.extract_certificate().fingerprint
+            == tsk.extract_certificate().fingerprint
+        )
+        signed = sign(restored.signer(), b"hello")
+        assert "PGP MESSAGE" in str(signed)
+
+    def test_setup_message_custom_passcode(self):
+        tsk = Tsk.generate("Alice <alice@example.org>")
+        message, passcode = autocrypt.generate_setup_message(tsk, "correct horse")
+        assert passcode == "correct horse"
+        assert "Passphrase-Format" not in message
+        assert "Passphrase-Begin" not in message
+        restored = autocrypt.parse_setup_message(message, passcode)
+        assert (
+            restored.extract_certificate().fingerprint
+            == tsk.extract_certificate().fingerprint
+        )
+
+        numeric = "-".join(["1234"] * 9)
+        message, _ = autocrypt.generate_setup_message(tsk, numeric)
+        assert "Passphrase-Format: numeric9x4" in message
+        assert "Passphrase-Begin: 12" in message
+
+    def test_setup_message_wrong_passcode(self):
+        tsk = Tsk.generate("Alice <alice@example.org>")
+        message, _ = autocrypt.generate_setup_message(tsk, "1234")
+        with pytest.raises(Exception):
+            autocrypt.parse_setup_message(message, "4321")
+
+
+class TestDane:
+    def test_owner_name(self):
+        # example from RFC 7929, section 7
+        assert dane.owner_name("hugh@example.com") == (
+            "c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6"
+            "._openpgpkey.example.com."
+        )
+
+    def test_owner_name_keeps_local_part_case(self):
+        assert dane.owner_name("Hugh@EXAMPLE.com") != dane.owner_name(
+            "hugh@example.com"
+        )
+        assert dane.owner_name("Hugh@EXAMPLE.com").endswith("._openpgpkey.example.com.")
+
+    def test_record_roundtrip(self):
+        cert = Tsk.generate(
+            user_ids=["Hugh <hugh@example.com>", "Hugh <hugh@other.invalid>"]
+        ).extract_certificate()
+        record = dane.generate_record(cert, "hugh@example.com", ttl=3600)
+        assert record.startswith(
+            dane.owner_name("hugh@example.com") + " 3600 IN OPENPGPKEY "
+        )
+
+        parsed = dane.parse_record(record)
+        assert parsed.fingerprint == cert.fingerprint
+        assert [str(u) for u in parsed.user_ids] == ["Hugh <hugh@example.com>"]
+
+    def test_generic_record_roundtrip(self):
+        cert = Tsk.generate("Hugh <hugh@example.com>").extract_certificate()
+        record = dane.generate_record(cert, "hugh@example.com", generic=True)
+        assert " IN TYPE61 \\# " in record
+        assert dane.parse_record(record).fingerprint == cert.fingerprint
+
+    def test_parse_multiline_record(self):
+        cert = Tsk.generate("Hugh <hugh@example.com>").extract_certificate()
+        owner, data = dane.generate_record(cert, "hugh@example.com").split(
+            " OPENPGPKEY "
+        )
+        chunks = [data[i : i + 64] for i in range(0, len(data), 64)]
+        record = owner + " OPENPGPKEY (\n" + "\n".join(chunks) + " )"
+        assert dane.parse_record(record).fingerprint == cert.fingerprint
+
+    def test_parse_invalid_record(self):
+        with pytest.raises(Exception):
+            dane.parse_record("example.com. IN A 192.0.2.1")
+
+
+class TestKeyring:
+    def test_encrypt_to_group(self):
+        alice = Tsk.generate("Alice <alice@example.org>")
+        bob = Tsk.generate("Bob <bob@example.org>")
+        keyring = Keyring([alice.extract_certificate(), bob.extract_certificate()])
+        keyring.add_group(
+            "ops@example.org",
+            [
+                alice.extract_certificate().fingerprint,
+                bob.extract_certificate().fingerprint,
+            ],
+        )
+        assert len(keyring.groups["ops@example.org"]) == 2
+
+        encrypted = encrypt(b"deploy", recipients=["ops@example.org"], keyring=keyring)
+        for member in (alice, bob):
+            assert decrypt(encrypted, decryptor=member.decryptor()).bytes == b"deploy"
+
+    def test_encrypt_to_email_and_cert(self):
+        alice = Tsk.generate("Alice <alice@example.org>")
+        bob = Tsk.generate("Bob <bob@example.org>")
+        keyring = Keyring([alice.extract_certificate()])
+
+        encrypted = encrypt(
+            b"hello",
+            recipients=["Alice@Example.org", bob.extract_certificate()],
+            keyring=keyring,
+        )
+        for member in (alice, bob):
+            assert decrypt(encrypted, decryptor=member.decryptor()).bytes == b"hello"
+
+    def test_encrypt_file_to_group(self, tmp_path):
+        alice = Tsk.generate("Alice <alice@example.org>")
+        keyring = Keyring()
+        keyring.add(alice.extract_certificate())
+        keyring.add_group("team", [alice.extract_certificate().fingerprint])
+
+        plaintext = tmp_path / "plain.txt"
+        plaintext.write_bytes(b"file content")
+        ciphertext = tmp_path / "cipher.pgp"
+        encrypt_file(plaintext, ciphertext, recipients=["team"], keyring=keyring)
+
+        decrypted = decrypt(ciphertext.read_bytes(), decryptor=alice.decryptor())
+        assert decrypted.bytes == b"file content"
+
+    def test_name_requires_keyring(self):
+        with pytest.raises(Exception, match="keyring"):
+            encrypt(b"hello", recipients=["ops@example.org"])
+
+    def test_unknown_name(self):
+        with pytest.raises(Exception, match="No group or certificate"):
+            encrypt(b"hello", recipients=["nobody@example.org"], keyring=Keyring())
+
+    def test_group_member_not_in_keyring(self):
+        stranger = Tsk.generate("Stranger").extract_certificate()
+        with pytest.raises(Exception):
+            Keyring().add_group("team", [stranger.fingerprint])
+
+    def test_group_member_without_encryption_subkey(self):
+        alice = Tsk.generate("Alice <alice@example.org>")
+        signer = Tsk.from_file(fixture_path("signing-key.asc")).extract_certificate()
+        keyring = Keyring([alice.extract_certificate(), signer])
+        keyring.add_group(
+            "team", [alice.extract_certificate().fingerprint, signer.fingerprint]
+        )
+        with pytest.raises(Exception, match=signer.fingerprint):
+            encrypt(b"hello", recipients=["team"], keyring=keyring)
+
+    def test_remove_group(self):
+        alice = Tsk.generate("Alice <alice@example.org>").extract_certificate()
+        keyring = Keyring([alice, alice])
+        assert len(keyring) == 1
+        keyring.add_group("team", [alice.fingerprint])
+        keyring.remove_group("team")
+        assert keyring.groups == {}
+        with pytest.raises(Exception):
+            keyring.remove_group("team")
+
+
+class TestEncryptor:
+    def test_write_chunks(self):
+        sender = Tsk.generate("Sender <sender@example.com>")
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        sink = io.BytesIO()
+
+        encryptor = Encryptor(
+            sink,
+            recipients=[receiver.extract_certificate()],
+            signer=sender.signer(),
+        )
+        for chunk in [b"Red ", b"Green ", b"Blue"]:
+            assert encryptor.write(chunk) == len(chunk)
+        assert not encryptor.closed
+        encryptor.close()
+        assert encryptor.closed
+        assert b"PGP MESSAGE" in sink.getvalue()
+
+        decrypted = decrypt(
+            sink.getvalue(),
+            decryptor=receiver.decryptor(),
+            store=lambda key_ids: [sender.extract_certificate()],
+        )
+        assert decrypted.bytes == b"Red Green Blue"
+        assert len(decrypted.valid_sigs) == 1
+
+    def test_context_manager(self):
+        sink = io.BytesIO()
+        with Encryptor(sink, passwords=["sekrit"], armor=False) as encryptor:
+            encryptor.write(b"hello ")
+            encryptor.write(b"world")
+        assert encryptor.closed
+        assert not sink.getvalue().startswith(b"-----")
+        assert decrypt(sink.getvalue(), passwords=["sekrit"]).bytes == b"hello world"
+
+    def test_large_input(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        content = os.urandom(1024 * 1024)
+        sink = io.BytesIO()
+        with Encryptor(sink, recipients=[receiver.extract_certificate()]) as encryptor:
+            for offset in range(0, len(content), 65536):
+                encryptor.write(content[offset : offset + 65536])
+        decrypted = decrypt(sink.getvalue(), decryptor=receiver.decryptor())
+        assert decrypted.bytes == content
+
+    def test_exception_leaves_message_unfinished(self):
+        sink = io.BytesIO()
+        with pytest.raises(RuntimeError):
+            with Encryptor(sink, passwords=["sekrit"]) as encryptor:
+                encryptor.write(b"partial")
+                raise RuntimeError("aborted")
+        assert encryptor.closed
+        with pytest.raises(Exception):
+            decrypt(sink.getvalue(), passwords=["sekrit"])
+
+    def test_write_after_close(self):
+        encryptor = Encryptor(io.BytesIO(), passwords=["sekrit"])
+        encryptor.close()
+        encryptor.close()
+        with pytest.raises(ValueError, match="closed"):
+            encryptor.write(b"too late")
+
+    def test_no_recipients(self):
+        with pytest.raises(Exception, match="non-empty"):
+            Encryptor(io.BytesIO())
+
+
+class TestMessageReader:
+    def test_open_decrypt_file_like(self):
+        sender = Tsk.generate("Sender <sender@example.com>")
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        content = os.urandom(300 * 1024)
+        encrypted = encrypt(
+            content,
+            recipients=[receiver.extract_certificate()],
+            signer=sender.signer(),
+            armor=False,
+        )
+
+        reader = open_decrypt(
+            io.BytesIO(encrypted),
+            decryptor=receiver.decryptor(),
+            store=lambda key_ids: [sender.extract_certificate()],
+        )
+        with pytest.raises(ValueError, match="whole message"):
+            reader.valid_sigs
+        chunks = list(reader)
+        assert len(chunks) > 1
+        assert b"".join(chunks) == content
+        assert reader.read() == b""
+        assert len(reader.valid_sigs) == 1
+        assert (
+            reader.valid_sigs[0].certificate
+            == sender.extract_certificate().fingerprint
+        )
+
+    def test_open_decrypt_path(self):
+        encrypted = encrypt(b"from a file", passwords=["sekrit"])
+        with tempfile.NamedTemporaryFile(delete=False, suffix=".pgp") as inp:
+            inp.write(encrypted)
+            input_path = inp.name
+        try:
+            with open_decrypt(input_path, passwords=["sekrit"]) as reader:
+                assert reader.read(4) == b"from"
+                assert reader.read() == b" a file"
+            assert reader.valid_sigs == []
+        finally:
+            os.unlink(input_path)
+
+    def test_failed_verification_stops_reading(self):
+        sender = Tsk.generate("Sender <sender@example.com>")
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        # Larger than the buffer sequoia verifies before returning any data.
+        content = os.urandom(30 * 1024 * 1024)
+        encrypted = encrypt(
+            content,
+            recipients=[receiver.extract_certificate()],
+            signer=sender.signer(),
+            armor=False,
+        )
+
+        reader = open_decrypt(
+            io.BytesIO(encrypted),
+            decryptor=receiver.decryptor(),
+            store=lambda key_ids: [],
+        )
+        received = 0
+        with pytest.raises(Exception, match="no valid signatures"):
+            while chunk := reader.read(1024 * 1024):
+                received += len(chunk)
+        assert received < len(content)
+        with pytest.raises(Exception, match="no valid signatures"):
+            reader.read()
+        with pytest.raises(ValueError, match="whole message"):
+            reader.valid_sigs
+
+    def test_open_decrypt_wrong_password(self):
+        encrypted = encrypt(b"secret", passwords=["sekrit"])
+        with pytest.raises(Exception):
+            open_decrypt(io.BytesIO(encrypted), passwords=["wrong"])
+
+    def test_open_decrypt_requires_key(self):
+        with pytest.raises(Exception, match="non-empty"):
+            open_decrypt(io.BytesIO(b""))
+
+    def test_open_verify(self, signing_key, signing_tsk):
+        signed = sign(signing_tsk.signer(), b"signed data")
+        with open_verify(
+            io.BytesIO(signed), store=lambda key_ids: [signing_key]
+        ) as reader:
+            assert reader.read() == b"signed data"
+        assert reader.valid_sigs[0].certificate == signing_key.fingerprint
+
+    def test_open_verify_unknown_signer(self, signing_tsk):
+        signed = sign(signing_tsk.signer(), b"signed data")
+        with pytest.raises(Exception, match="no valid signatures"):
+            with open_verify(io.BytesIO(signed), store=lambda key_ids: []) as reader:
+                reader.read()
+
+    def test_read_after_close(self):
+        encrypted = encrypt(b"secret", passwords=["sekrit"])
+        reader = open_decrypt(io.BytesIO(encrypted), passwords=["sekrit"])
+        reader.close()
+        with pytest.raises(ValueError, match="closed"):
+            reader.read()
+
+
+class TestCipherSelection:
+    def test_cipher_for_passwords(self):
+        encrypted = encrypt(
+            b"hello",
+            passwords=["sekrit"],
+            armor=False,
+            cipher=SymmetricAlgorithm.AES256,
+        )
+        skesk = next(p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.SKESK)
+        assert skesk.body[0] == 4
+        assert skesk.body[1] == 9  # AES-256
+        assert decrypt(encrypted, passwords=["sekrit"]).bytes == b"hello"
+
+    def test_aead_for_passwords(self):
+        encrypted = encrypt(
+            b"hello",
+            passwords=["sekrit"],
+            armor=False,
+            cipher=SymmetricAlgorithm.AES256,
+            aead=AEADAlgorithm.GCM,
+        )
+        seip = next(p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.SEIP)
+        assert list(seip.body[:3]) == [2, 9, 3]  # SEIPDv2, AES-256, GCM
+        assert decrypt(encrypted, passwords=["sekrit"]).bytes == b"hello"
+
+    def test_aead_for_rfc9580_recipient(self):
+        receiver = Tsk.generate("V6 <v6@example.com>", profile=Profile.RFC9580)
+        encrypted = encrypt(
+            b"hello",
+            recipients=[receiver.extract_certificate()],
+            armor=False,
+            aead=AEADAlgorithm.OCB,
+        )
+        seip = next(p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.SEIP)
+        assert seip.body[0] == 2  # SEIPDv2
+        assert seip.body[2] == 2  # OCB
+        decrypted = decrypt(encrypted, decryptor=receiver.decryptor())
+        assert decrypted.bytes == b"hello"
+
+    def test_aead_unsupported_by_recipient(self, wiktor_fresh_key):
+        with pytest.raises(Exception, match="does not support AEAD"):
+            encrypt(b"hello", recipients=[wiktor_fresh_key], aead=AEADAlgorithm.OCB)
+
+    def test_cipher_unsupported_by_recipient(self, wiktor_fresh_key):
+        with pytest.raises(Exception, match="does not support"):
+            encrypt(
+                b"hello",
+                recipients=[wiktor_fresh_key],
+                cipher=SymmetricAlgorithm.TripleDES,
+            )
+
+    @pytest.mark.parametrize(
+        "cipher",
+        [
+            SymmetricAlgorithm.IDEA,
+            SymmetricAlgorithm.TripleDES,
+            SymmetricAlgorithm.CAST5,
+        ],
+    )
+    def test_cipher_rejected_by_policy(self, cipher):
+        with pytest.raises(Exception, match="rejected by the policy"):
+            encrypt(b"hello", passwords=["sekrit"], cipher=cipher)
+
+    def test_aead_picks_cipher_of_recipient(self):
+        receiver = Tsk.from_file(fixture_path("aes128-gcm.asc"))
+        encrypted, report = encrypt(
+            b"hello",
+            recipients=[receiver.extract_certificate()],
+            armor=False,
+            aead=AEADAlgorithm.GCM,
+            report=True,
+        )
+        assert report.cipher == SymmetricAlgorithm.AES128
+        seip = next(p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.SEIP)
+        assert list(seip.body[:3]) == [2, 7, 3]  # SEIPDv2, AES-128, GCM
+        decrypted = decrypt(encrypted, decryptor=receiver.decryptor())
+        assert decrypted.bytes == b"hello"
+
+    def test_aead_suite_unsupported_by_recipient(self):
+        receiver = Cert.from_file(fixture_path("aes128-gcm.asc"))
+        with pytest.raises(Exception, match="does not support AES-256 with GCM"):
+            encrypt(
+                b"hello",
+                recipients=[receiver],
+                cipher=SymmetricAlgorithm.AES256,
+                aead=AEADAlgorithm.GCM,
+            )
+
+    def test_cipher_from_recipient_preferences(self, wiktor_fresh_key):
+        encrypted = encrypt(
+            b"hello", recipients=[wiktor_fresh_key], cipher=SymmetricAlgorithm.AES192
+        )
+        assert b"PGP MESSAGE" in encrypted
+
+    def test_encrypt_file_with_cipher(self):
+        with tempfile.NamedTemporaryFile(delete=False, suffix=".txt") as inp:
+            inp.write(b"content to encrypt")
+            input_path = inp.name
+        with tempfile.NamedTemporaryFile(delete=False, suffix=".pgp") as out:
+            output_path = out.name
+        try:
+            encrypt_file(
+                input_path,
+                output_path,
+                passwords=["sekrit"],
+                cipher=SymmetricAlgorithm.AES128,
+                aead=AEADAlgorithm.OCB,
+            )
+            decrypted = decrypt(open(output_path, "rb").read(), passwords=["sekrit"])
+            assert decrypted.bytes == b"content to encrypt"
+        finally:
+            os.unlink(input_path)
+            os.unlink(output_path)
+
+
+class TestCompression:
+    CONTENT = b"a highly repetitive text export\n" * 4096
+
+    def test_encrypt_compressed(self):
+        plain = encrypt(self.CONTENT, passwords=["sekrit"], armor=False)
+        compressed = encrypt(
+            self.CONTENT,
+            passwords=["sekrit"],
+            armor=False,
+            compression=CompressionAlgorithm.Zlib,
+        )
+        assert len(compressed) < len(plain) // 10
+        assert decrypt(compressed, passwords=["sekrit"]).bytes == self.CONTENT
+
+    def test_encrypt_signed_compressed(self):
+        sender = Tsk.generate("Sender <sender@example.com>")
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            self.CONTENT,
+            recipients=[receiver.extract_certificate()],
+            signer=sender.signer(),
+            compression=CompressionAlgorithm.Zip,
+        )
+        decrypted = decrypt(
+            encrypted,
+            decryptor=receiver.decryptor(),
+            store=lambda key_ids: [sender.extract_certificate()],
+        )
+        assert decrypted.bytes == self.CONTENT
+        assert len(decrypted.valid_sigs) == 1
+
+    def test_sign_compressed(self, signing_key, signing_tsk):
+        signed = sign(
+            signing_tsk.signer(),
+            self.CONTENT,
+            armor=False,
+            compression=CompressionAlgorithm.Zlib,
+        )
+        assert len(signed) < len(self.CONTENT) // 10
+        assert next(iter(PacketPile.from_bytes(signed))).tag == Tag.CompressedData
+        verified = verify(signed, store=lambda key_ids: [signing_key])
+        assert verified.bytes == self.CONTENT
+        assert len(verified.valid_sigs) == 1
+
+    def test_sign_file_compressed(self, signing_key, signing_tsk, tmp_path):
+        input_path = tmp_path / "export.txt"
+        output_path = tmp_path / "export.txt.pgp"
+        input_path.write_bytes(self.CONTENT)
+        sign_file(
+            signing_tsk.signer(),
+            input_path,
+            output_path,
+            compression=CompressionAlgorithm.Zip,
+        )
+        verified = verify(file=output_path, store=lambda key_ids: [signing_key])
+        assert verified.bytes == self.CONTENT
+
+    def test_detached_compression_rejected(self, signing_tsk):
+        with pytest.raises(Exception, match="inline signatures"):
+            sign(
+                signing_tsk.signer(),
+                self.CONTENT,
+                mode=SignatureMode.DETACHED,
+                compression=CompressionAlgorithm.Zlib,
+            )
+
+    def test_bzip2(self):
+        try:
+            encrypted = encrypt(
+                self.CONTENT,
+                passwords=["sekrit"],
+                compression=CompressionAlgorithm.BZip2,
+            )
+        except Exception as e:
+            assert "not supported" in str(e)
+        else:
+            assert decrypt(encrypted, passwords=["sekrit"]).bytes == self.CONTENT
+
+
+class TestHiddenRecipients:
+    def test_wildcard_key_id(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            b"leak",
+            hidden_recipients=[receiver.extract_certificate()],
+            armor=False,
+        )
+        pkesk = next(p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.PKESK)
+        # version 6 PKESKs leave out the key version and fingerprint
+        assert list(pkesk.body[:2]) == [6, 0]
+        assert decrypt(encrypted, decryptor=receiver.decryptor()).bytes == b"leak"
+
+    def test_wildcard_key_id_seipdv1(self, wiktor_fresh_key):
+        encrypted = encrypt(b"leak", hidden_recipients=[wiktor_fresh_key], armor=False)
+        pkesk = next(p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.PKESK)
+        assert pkesk.body[0] == 3
+        assert pkesk.body[1:9] == bytes(8)
+
+    def test_mixed_recipients(self):
+        visible = Tsk.generate("Visible <visible@example.com>")
+        hidden = Tsk.generate("Hidden <hidden@example.com>")
+        encrypted = encrypt(
+            b"leak",
+            recipients=[visible.extract_certificate()],
+            hidden_recipients=[hidden.extract_certificate()],
+        )
+        for tsk in [visible, hidden]:
+            assert decrypt(encrypted, decryptor=tsk.decryptor()).bytes == b"leak"
+
+    def test_anonymous_rfc9580_recipient(self):
+        receiver = Tsk.generate("V6 <v6@example.com>", profile=Profile.RFC9580)
+        encrypted = encrypt(b"leak", hidden_recipients=[receiver.extract_certificate()])
+        assert decrypt(encrypted, decryptor=receiver.decryptor()).bytes == b"leak"
+
+    def test_wrong_key(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        other = Tsk.generate("Other <other@example.com>")
+        encrypted = encrypt(b"leak", hidden_recipients=[receiver.extract_certificate()])
+        with pytest.raises(Exception):
+            decrypt(encrypted, decryptor=other.decryptor())
+
+    def test_encrypt_file(self, tmp_path):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        input_path = tmp_path / "leak.txt"
+        output_path = tmp_path / "leak.pgp"
+        input_path.write_bytes(b"leak")
+        encrypt_file(
+            input_path,
+            output_path,
+            hidden_recipients=[receiver.extract_certificate()],
+        )
+        decrypted = decrypt(output_path.read_bytes(), decryptor=receiver.decryptor())
+        assert decrypted.bytes == b"leak"
+
+
+class TestLiteralMetadata:
+    DATE = datetime(2024, 1, 2, 3, 4, 5, tzinfo=timezone.utc)
+
+    def test_encrypt_metadata(self):
+        encrypted = encrypt(
+            b"report",
+            passwords=["sekrit"],
+            filename="report.txt",
+            date=self.DATE,
+            format=DataFormat.Unicode,
+        )
+        decrypted = decrypt(encrypted, passwords=["sekrit"])
+        assert decrypted.bytes == b"report"
+        assert decrypted.filename == "report.txt"
+        assert decrypted.literal_date == self.DATE
+        assert decrypted.format == DataFormat.Unicode
+
+    def test_defaults(self):
+        encrypted = encrypt(b"report", passwords=["sekrit"])
+        decrypted = decrypt(encrypted, passwords=["sekrit"])
+        assert decrypted.filename is None
+        assert decrypted.literal_date is None
+        assert decrypted.format == DataFormat.Binary
+
+    def test_decrypt_file_metadata(self, tmp_path):
+        input_path = tmp_path / "input.txt"
+        encrypted_path = tmp_path / "input.pgp"
+        output_path = tmp_path / "output.txt"
+        input_path.write_bytes(b"report")
+        encrypt_file(
+            input_path, encrypted_path, passwords=["sekrit"], filename="input.txt"
+        )
+        decrypted = decrypt_file(encrypted_path, output_path, passwords=["sekrit"])
+        assert decrypted.filename == "input.txt"
+        assert output_path.read_bytes() == b"report"
+
+    def test_sign_metadata(self, signing_key, signing_tsk):
+        signed = sign(
+            signing_tsk.signer(),
+            b"report",
+            filename="report.txt",
+            date=self.DATE,
+        )
+        verified = verify(signed, store=lambda key_ids: [signing_key])
+        assert verified.filename == "report.txt"
+        assert verified.literal_date == self.DATE
+        assert verified.format == DataFormat.Binary
+
+    def test_detached_metadata_rejected(self, signing_tsk):
+        with pytest.raises(Exception, match="inline signatures"):
+            sign(
+                signing_tsk.signer(),
+                b"report",
+                mode=SignatureMode.DETACHED,
+                filename="report.txt",
+            )
+
+    def test_detached_verify_has_no_metadata(self, signing_key, signing_tsk):
+        signature = Sig.from_bytes(
+            sign(signing_tsk.signer(), b"report", mode=SignatureMode.DETACHED)
+        )
+        verified = verify(
+            b"report", store=lambda key_ids: [signing_key], signature=signature
+        )
+        assert verified.filename is None
+        assert verified.format is None
+
+
+class TestSessionKeys:
+    def test_export_and_reuse(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            b"disclosed",
+            recipients=[receiver.extract_certificate()],
+            cipher=SymmetricAlgorithm.AES256,
+        )
+        decrypted = decrypt(
+            encrypted, decryptor=receiver.decryptor(), export_session_key=True
+        )
+        algo, key = decrypted.session_key.split(":")
+        assert algo == "9"
+        assert len(bytes.fromhex(key)) == 32
+
+        disclosed = decrypt(encrypted, session_keys=[decrypted.session_key])
+        assert disclosed.bytes == b"disclosed"
+
+    def test_not_exported_by_default(self):
+        encrypted = encrypt(b"secret", passwords=["sekrit"])
+        assert decrypt(encrypted, passwords=["sekrit"]).session_key is None
+
+    def test_seipdv2(self):
+        encrypted = encrypt(b"disclosed", passwords=["sekrit"], aead=AEADAlgorithm.OCB)
+        session_key = decrypt(
+            encrypted, passwords=["sekrit"], export_session_key=True
+        ).session_key
+        assert decrypt(encrypted, session_keys=[session_key]).bytes == b"disclosed"
+        # the algorithm is stored in SEIPDv2 packets and may be omitted
+        key = session_key.split(":")[1]
+        assert decrypt(encrypted, session_keys=[key]).bytes == b"disclosed"
+
+    def test_wrong_session_key(self):
+        encrypted = encrypt(b"secret", passwords=["sekrit"])
+        with pytest.raises(Exception):
+            decrypt(encrypted, session_keys=["9:" + "00" * 32])
+
+    def test_malformed_session_key(self):
+        encrypted = encrypt(b"secret", passwords=["sekrit"])
+        with pytest.raises(Exception, match="session key"):
+            decrypt(encrypted, session_keys=["aes:zz"])
+
+    def test_decrypt_file(self, tmp_path):
+        encrypted_path = tmp_path / "message.pgp"
+        output_path = tmp_path / "message.txt"
+        encrypted = encrypt(b"disclosed", passwords=["sekrit"])
+        encrypted_path.write_bytes(encrypted)
+        session_key = decrypt(
+            encrypted, passwords=["sekrit"], export_session_key=True
+        ).session_key
+        decrypted = decrypt_file(
+            encrypted_path,
+            output_path,
+            session_keys=[session_key],
+            export_session_key=True,
+        )
+        assert decrypted.session_key == session_key
+        assert output_path.read_bytes() == b"disclosed"
+
+
+class TestReencrypt:
+    @staticmethod
+    def seip_body(encrypted):
+        return next(
+            p for p in PacketPile.from_bytes(encrypted) if p.tag == Tag.SEIP
+        ).body
+
+    def test_add_recipient(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        bob = Tsk.generate("Bob <bob@example.com>")
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        reencrypted = reencrypt(
+            encrypted, alice.decryptor(), add_recipients=[bob.extract_certificate()]
+        )
+        for tsk in [alice, bob]:
+            assert decrypt(reencrypted, decryptor=tsk.decryptor()).bytes == b"shared"
+        assert self.seip_body(reencrypted) == self.seip_body(encrypted)
+
+    def test_remove_recipient(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        bob = Tsk.generate("Bob <bob@example.com>")
+        encrypted = encrypt(
+            b"shared",
+            recipients=[alice.extract_certificate(), bob.extract_certificate()],
+        )
+        reencrypted = reencrypt(
+            encrypted, alice.decryptor(), remove_recipients=[bob.extract_certificate()]
+        )
+        assert decrypt(reencrypted, decryptor=alice.decryptor()).bytes == b"shared"
+        with pytest.raises(Exception):
+            decrypt(reencrypted, decryptor=bob.decryptor())
+
+    def test_add_password(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        reencrypted = reencrypt(
+            encrypted, alice.decryptor(), passwords=["sekrit"], armor=False
+        )
+        assert not reencrypted.startswith(b"-----BEGIN PGP MESSAGE-----")
+        assert decrypt(reencrypted, passwords=["sekrit"]).bytes == b"shared"
+
+    def test_seipdv2(self):
+        alice = Tsk.generate("Alice <alice@example.com>", profile=Profile.RFC9580)
+        bob = Tsk.generate("Bob <bob@example.com>", profile=Profile.RFC9580)
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        reencrypted = reencrypt(
+            encrypted,
+            alice.decryptor(),
+            add_recipients=[bob.extract_certificate()],
+            passwords=["sekrit"],
+        )
+        assert decrypt(reencrypted, decryptor=bob.decryptor()).bytes == b"shared"
+        assert decrypt(reencrypted, passwords=["sekrit"]).bytes == b"shared"
+        assert self.seip_body(reencrypted) == self.seip_body(encrypted)
+
+    def test_seipdv1_recipient_rejected(self, wiktor_fresh_key):
+        alice = Tsk.generate("Alice <alice@example.com>", profile=Profile.RFC9580)
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        with pytest.raises(Exception, match="does not support AEAD"):
+            reencrypt(encrypted, alice.decryptor(), add_recipients=[wiktor_fresh_key])
+
+    def test_argon2(self):
+        alice = Tsk.generate("Alice <alice@example.com>", profile=Profile.RFC9580)
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        reencrypted = reencrypt(
+            encrypted, alice.decryptor(), passwords=["sekrit"], s2k=S2K.Argon2
+        )
+        assert inspect_message(reencrypted).skesks[0].s2k == S2K.Argon2
+        assert decrypt(reencrypted, passwords=["sekrit"]).bytes == b"shared"
+
+    @staticmethod
+    def has_checksum(armored):
+        return any(
+            line.startswith(b"=") and len(line) == 5 for line in armored.splitlines()
+        )
+
+    def test_armor_checksum(self, wiktor_fresh_key):
+        alice = Tsk.generate("Alice <alice@example.com>", profile=Profile.RFC9580)
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        reencrypted = reencrypt(encrypted, alice.decryptor(), passwords=["sekrit"])
+        # RFC 9580 drops the CRC24 checksum for SEIPDv2 messages.
+        assert not self.has_checksum(reencrypted)
+        assert decrypt(reencrypted, passwords=["sekrit"]).bytes == b"shared"
+
+        bob = Tsk.generate("Bob <bob@example.com>")
+        encrypted = encrypt(
+            b"shared", recipients=[bob.extract_certificate(), wiktor_fresh_key]
+        )
+        assert inspect_message(encrypted).seipd_version == 1
+        reencrypted = reencrypt(encrypted, bob.decryptor(), passwords=["sekrit"])
+        assert self.has_checksum(reencrypted)
+
+    def test_argon2_seipdv1(self, wiktor_fresh_key):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        encrypted = encrypt(
+            b"shared", recipients=[alice.extract_certificate(), wiktor_fresh_key]
+        )
+        assert inspect_message(encrypted).seipd_version == 1
+        with pytest.raises(Exception, match="Argon2 requires a SEIPDv2 message"):
+            reencrypt(
+                encrypted, alice.decryptor(), passwords=["sekrit"], s2k=S2K.Argon2
+            )
+        reencrypted = reencrypt(encrypted, alice.decryptor(), passwords=["sekrit"])
+        assert decrypt(reencrypted, passwords=["sekrit"]).bytes == b"shared"
+
+    def test_file(self, tmp_path):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        bob = Tsk.generate("Bob <bob@example.com>")
+        content = os.urandom(1 << 20)
+        input_path = tmp_path / "message.pgp"
+        output_path = tmp_path / "reencrypted.pgp"
+        input_path.write_bytes(
+            encrypt(content, recipients=[alice.extract_certificate()], armor=False)
+        )
+        reencrypt_file(
+            input_path,
+            output_path,
+            alice.decryptor(),
+            add_recipients=[bob.extract_certificate()],
+            armor=False,
+        )
+        reencrypted = output_path.read_bytes()
+        assert decrypt(reencrypted, decryptor=bob.decryptor()).bytes == content
+        assert self.seip_body(reencrypted) == self.seip_body(input_path.read_bytes())
+
+    def test_wrong_key(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        bob = Tsk.generate("Bob <bob@example.com>")
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        with pytest.raises(Exception, match="No key to decrypt message"):
+            reencrypt(
+                encrypted, bob.decryptor(), add_recipients=[bob.extract_certificate()]
+            )
+
+    def test_no_recipients_left(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        encrypted = encrypt(b"shared", recipients=[alice.extract_certificate()])
+        with pytest.raises(Exception, match="no recipients"):
+            reencrypt(
+                encrypted,
+                alice.decryptor(),
+                remove_recipients=[alice.extract_certificate()],
+            )
+
+
+class TestMultipleSigners:
+    @staticmethod
+    def signers():
+        legacy = Tsk.generate("Legacy <legacy@example.com>")
+        pqc = Tsk.generate("PQC <pqc@example.com>", profile=Profile.RFC9580)
+        return legacy, pqc
+
+    @staticmethod
+    def fingerprints(decrypted):
+        return {sig.certificate for sig in decrypted.valid_sigs}
+
+    def test_sign(self):
+        legacy, pqc = self.signers()
+        certs = [legacy.extract_certificate(), pqc.extract_certificate()]
+        signed = sign(legacy.signer(), b"migrating", signers=[pqc.signer()])
+        verified = verify(signed, lambda key_ids: certs)
+        assert verified.bytes == b"migrating"
+        assert self.fingerprints(verified) == {cert.fingerprint for cert in certs}
+
+    def test_clear(self):
+        legacy, pqc = self.signers()
+        certs = [legacy.extract_certificate(), pqc.extract_certificate()]
+        signed = sign(
+            legacy.signer(),
+            b"migrating",
+            mode=SignatureMode.CLEAR,
+            signers=[pqc.signer()],
+        )
+        verified = verify(signed, lambda key_ids: certs)
+        assert len(verified.valid_sigs) == 2
+
+    def test_sign_file(self, tmp_path):
+        legacy, pqc = self.signers()
+        certs = [legacy.extract_certificate(), pqc.extract_certificate()]
+        input_path = tmp_path / "input.txt"
+        output_path = tmp_path / "output.pgp"
+        input_path.write_bytes(b"migrating")
+        sign_file(legacy.signer(), input_path, output_path, signers=[pqc.signer()])
+        verified = verify(output_path.read_bytes(), lambda key_ids: certs)
+        assert len(verified.valid_sigs) == 2
+
+    def test_encrypt(self):
+        legacy, pqc = self.signers()
+        certs = [legacy.extract_certificate(), pqc.extract_certificate()]
+        encrypted = encrypt(
+            b"migrating",
+            passwords=["sekrit"],
+            signers=[legacy.signer(), pqc.signer()],
+        )
+        decrypted = decrypt(
+            encrypted, passwords=["sekrit"], store=lambda key_ids: certs
+        )
+        assert decrypted.bytes == b"migrating"
+        assert self.fingerprints(decrypted) == {cert.fingerprint for cert in certs}
+
+    def test_encryptor(self):
+        legacy, pqc = self.signers()
+        certs = [legacy.extract_certificate(), pqc.extract_certificate()]
+        sink = io.BytesIO()
+        with Encryptor(
+            sink, passwords=["sekrit"], signer=legacy.signer(), signers=[pqc.signer()]
+        ) as encryptor:
+            encryptor.write(b"migrating")
+        decrypted = decrypt(
+            sink.getvalue(), passwords=["sekrit"], store=lambda key_ids: certs
+        )
+        assert len(decrypted.valid_sigs) == 2
+
+
+class TestPadding:
+    @staticmethod
+    def encrypted_length(data, padding):
+        return len(encrypt(data, passwords=["sekrit"], padding=padding, armor=False))
+
+    def test_buckets(self):
+        short = self.encrypted_length(b"yes", Padding.BUCKETS)
+        long = self.encrypted_length(b"x" * 400, Padding.BUCKETS)
+        assert short == long
+        assert short > 512
+
+    def test_padme(self):
+        data = b"x" * 4000
+        padded = self.encrypted_length(data, Padding.PADME)
+        assert padded > self.encrypted_length(data, None)
+        assert padded < 4000 * 1.12 + 200
+
+    def test_none(self):
+        data = b"x" * 400
+        unpadded = self.encrypted_length(data, None)
+        assert self.encrypted_length(data, Padding.NONE) == unpadded
+        assert self.encrypted_length(b"yes", Padding.NONE) < unpadded
+
+    @pytest.mark.parametrize("padding", [Padding.BUCKETS, Padding.PADME])
+    def test_roundtrip(self, padding):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            b"padded",
+            recipients=[receiver.extract_certificate()],
+            signer=receiver.signer(),
+            padding=padding,
+        )
+        decrypted = decrypt(
+            encrypted,
+            decryptor=receiver.decryptor(),
+            store=lambda key_ids: [receiver.extract_certificate()],
+        )
+        assert decrypted.bytes == b"padded"
+        assert len(decrypted.valid_sigs) == 1
+
+    def test_seipdv1_recipient_rejected(self, wiktor_fresh_key):
+        with pytest.raises(Exception, match="Padding requires SEIPDv2"):
+            encrypt(b"yes", recipients=[wiktor_fresh_key], padding=Padding.BUCKETS)
+
+    def test_encryptor(self):
+        sink = io.BytesIO()
+        with Encryptor(
+            sink, passwords=["sekrit"], padding=Padding.BUCKETS
+        ) as encryptor:
+            encryptor.write(b"yes")
+        assert len(sink.getvalue()) > 512
+        assert decrypt(sink.getvalue(), passwords=["sekrit"]).bytes == b"yes"
+
+
+class TestEncryptionReport:
+    def test_report(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        cert = receiver.extract_certificate()
+        encrypted, report = encrypt(b"hello", recipients=[cert], report=True)
+        assert isinstance(report, EncryptionReport)
+        assert decrypt(encrypted, decryptor=receiver.decryptor()).bytes == b"hello"
+        [recipient] = report.recipients
+        assert recipient.certificate == cert.fingerprint
+        assert len(recipient.subkeys) == 1
+        assert recipient.subkeys[0] != cert.fingerprint
+        assert not recipient.expired_fallback
+        assert not recipient.hidden
+        assert report.cipher == SymmetricAlgorithm.AES256
+        assert report.aead == AEADAlgorithm.OCB
+
+    def test_no_report_by_default(self):
+        assert isinstance(encrypt(b"hello", passwords=["sekrit"]), bytes)
+
+    def test_algorithms(self):
+        _, report = encrypt(
+            b"hello",
+            passwords=["sekrit"],
+            cipher=SymmetricAlgorithm.AES128,
+            aead=AEADAlgorithm.GCM,
+            report=True,
+        )
+        assert report.cipher == SymmetricAlgorithm.AES128
+        assert report.aead == AEADAlgorithm.GCM
+        assert report.seipd_version == 2
+        assert report.recipients == []
+        assert repr(report) == "<EncryptionReport cipher=AES128 aead=GCM recipients=0>"
+
+    def test_preferences(self, wiktor_fresh_key):
+        _, report = encrypt(b"hello", recipients=[wiktor_fresh_key], report=True)
+        assert report.cipher == SymmetricAlgorithm.AES256
+        assert report.aead is None
+        assert report.seipd_version == 1
+        assert "aead=None" in repr(report)
+
+    def test_rfc9580_recipient(self):
+        receiver = Tsk.generate("V6 <v6@example.com>", profile=Profile.RFC9580)
+        encrypted, report = encrypt(
+            b"hello", recipients=[receiver.extract_certificate()], report=True
+        )
+        assert report.seipd_version == 2
+        assert decrypt(encrypted, decryptor=receiver.decryptor()).bytes == b"hello"
+
+    def test_expired_fallback(self, wiktor_key):
+        _, report = encrypt(b"hello", recipients=[wiktor_key], report=True)
+        assert report.recipients[0].expired_fallback
+
+    def test_hidden(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        _, report = encrypt(
+            b"hello", hidden_recipients=[receiver.extract_certificate()], report=True
+        )
+        assert report.recipients[0].hidden
+
+    def test_encrypt_file(self, tmp_path):
+        input_path = tmp_path / "input.txt"
+        output_path = tmp_path / "output.pgp"
+        input_path.write_bytes(b"hello")
+        assert encrypt_file(input_path, output_path, passwords=["sekrit"]) is None
+        report = encrypt_file(
+            input_path, output_path, passwords=["sekrit"], report=True
+        )
+        assert report.seipd_version == 1
+
+
+class TestRecipient:
+    def test_defaults(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        recipient = Recipient(receiver.extract_certificate())
+        assert recipient.purpose == "any"
+        assert recipient.subkeys == []
+        assert not recipient.allow_expired
+        encrypted = encrypt(b"backup", recipients=[recipient])
+        assert decrypt(encrypted, decryptor=receiver.decryptor()).bytes == b"backup"
+
+    @pytest.mark.parametrize(
+        "purpose, subkey",
+        [
+            ("storage", "6125539fe4b0117c79f058b19b0bff38f4b257f4"),
+            ("transport", "17553b66a5cf9f56ad95550cb960e0597588b467"),
+        ],
+    )
+    def test_purpose(self, purpose, subkey):
+        # The certificate has separate storage-only and transport-only subkeys.
+        receiver = Tsk.from_file(fixture_path("split-encryption.asc"))
+        recipient = Recipient(receiver.extract_certificate(), purpose=purpose)
+        encrypted, report = encrypt(b"backup", recipients=[recipient], report=True)
+        assert report.recipients[0].subkeys == [subkey]
+        decrypted = decrypt(encrypted, decryptor=receiver.decryptor())
+        assert decrypted.bytes == b"backup"
+        assert decrypted.decryption_key == subkey
+
+    def test_unknown_purpose(self):
+        cert = Tsk.generate("Receiver <receiver@example.com>").extract_certificate()
+        with pytest.raises(Exception, match="Unknown key purpose"):
+            Recipient(cert, purpose="signing")
+
+    def test_subkeys(self):
+        cert = Tsk.generate("Receiver <receiver@example.com>").extract_certificate()
+        _, report = encrypt(b"backup", recipients=[cert], report=True)
+        subkeys = report.recipients[0].subkeys
+        recipient = Recipient(cert, subkeys=subkeys)
+        _, report = encrypt(b"backup", recipients=[recipient], report=True)
+        assert report.recipients[0].subkeys == subkeys
+
+    def test_unsuitable_subkey(self):
+        cert = Tsk.generate("Receiver <receiver@example.com>").extract_certificate()
+        recipient = Recipient(cert, subkeys=[cert.fingerprint])
+        with pytest.raises(Exception, match="not a suitable encryption key"):
+            encrypt(b"backup", recipients=[recipient])
+
+    def test_invalid_fingerprint(self):
+        cert = Tsk.generate("Receiver <receiver@example.com>").extract_certificate()
+        with pytest.raises(Exception, match="Invalid fingerprint"):
+            Recipient(cert, subkeys=["not a fingerprint"])
+
+    def test_expired_rejected(self, wiktor_key):
+        with pytest.raises(Exception, match="expired"):
+            encrypt(b"backup", recipients=[Recipient(wiktor_key)])
+
+    def test_expired_allowed(self, wiktor_key):
+        recipient = Recipient(wiktor_key, allow_expired=True)
+        _, report = encrypt(b"backup", recipients=[recipient], report=True)
+        assert report.recipients[0].expired_fallback
+
+    def test_hidden(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        recipient = Recipient(receiver.extract_certificate(), purpose="storage")
+        encrypted = encrypt(b"backup", hidden_recipients=[recipient])
+        assert decrypt(encrypted, decryptor=receiver.decryptor()).bytes == b"backup"
+
+
+class TestMultipleDecryptors:
+    @staticmethod
+    def identities():
+        return [Tsk.generate(f"Identity {i} <id{i}@example.com>") for i in range(3)]
+
+    def test_list_of_decryptors(self):
+        identities = self.identities()
+        encrypted = encrypt(b"hello", recipients=[identities[2].extract_certificate()])
+        decryptors = [tsk.decryptor() for tsk in identities]
+        assert decrypt(encrypted, decryptor=decryptors).bytes == b"hello"
+
+    def test_list_of_tsks(self):
+        identities = self.identities()
+        encrypted = encrypt(b"hello", recipients=[identities[1].extract_certificate()])
+        assert decrypt(encrypted, decryptor=identities).bytes == b"hello"
+
+    def test_single_tsk(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(b"hello", recipients=[receiver.extract_certificate()])
+        assert decrypt(encrypted, decryptor=receiver).bytes == b"hello"
+
+    def test_mixed(self):
+        identities = self.identities()
+        encrypted = encrypt(b"hello", recipients=[identities[0].extract_certificate()])
+        decryptors = [identities[1].decryptor(), identities[0]]
+        assert decrypt(encrypted, decryptor=decryptors).bytes == b"hello"
+
+    def test_hidden_recipient(self):
+        identities = self.identities()
+        encrypted = encrypt(
+            b"hello", hidden_recipients=[identities[2].extract_certificate()]
+        )
+        assert decrypt(encrypted, decryptor=identities).bytes == b"hello"
+
+    def test_no_matching_key(self):
+        identities = self.identities()
+        other = Tsk.generate("Other <other@example.com>")
+        encrypted = encrypt(b"hello", recipients=[other.extract_certificate()])
+        with pytest.raises(Exception):
+            decrypt(encrypted, decryptor=identities)
+
+    def test_open_decrypt(self):
+        identities = self.identities()
+        encrypted = encrypt(b"hello", recipients=[identities[2].extract_certificate()])
+        with open_decrypt(io.BytesIO(encrypted), decryptor=identities) as reader:
+            assert reader.read() == b"hello"
+
+    def test_decrypt_file(self, tmp_path):
+        identities = self.identities()
+        input_path = tmp_path / "message.pgp"
+        output_path = tmp_path / "message.txt"
+        input_path.write_bytes(
+            encrypt(b"hello", recipients=[identities[1].extract_certificate()])
+        )
+        decrypt_file(input_path, output_path, decryptor=identities)
+        assert output_path.read_bytes() == b"hello"
+
+
+class TestHistoricDecryption:
+    @staticmethod
+    def revoked(tsk):
+        revocation = tsk.extract_certificate().revoke(certifier=tsk.certifier())
+        return Tsk.from_bytes(bytes(tsk) + bytes(revocation))
+
+    def test_revoked_key(self):
+        tsk = Tsk.generate("Archive <archive@example.com>")
+        encrypted = encrypt(b"old mail", recipients=[tsk.extract_certificate()])
+        revoked = self.revoked(tsk)
+        assert revoked.extract_certificate().is_revoked
+        assert decrypt(encrypted, decryptor=revoked.decryptor()).bytes == b"old mail"
+
+    def test_exclude_compromised(self):
+        tsk = Tsk.generate("Archive <archive@example.com>")
+        revoked = self.revoked(tsk)
+        with pytest.raises(Exception, match="No suitable decryption subkey"):
+            revoked.decryptor(exclude_compromised=True)
+
+    def test_expired_key(self):
+        tsk = Tsk.generate("Archive <archive@example.com>", validity_seconds=1)
+        time.sleep(2)
+        recipient = Recipient(tsk.extract_certificate(), allow_expired=True)
+        encrypted = encrypt(b"old mail", recipients=[recipient])
+        assert decrypt(encrypted, decryptor=tsk.decryptor()).bytes == b"old mail"
+
+    def test_rotated_out_subkey(self):
+        # The old encryption subkey expired in 2016 and is bound with SHA-1,
+        # which the standard policy no longer accepts.
+        tsk = Tsk.from_file(fixture_path("rotated-key.asc"))
+        with open(fixture_path("rotated-message.asc"), "rb") as f:
+            encrypted = f.read()
+        decrypted = decrypt(encrypted, decryptor=tsk.decryptor())
+        assert decrypted.bytes == b"sent to the old subkey"
+        assert decrypted.decryption_key == "14643b5bb704c608e0ece654ab9d077b5e21d3ac"
+
+
+class TestPasswordCallback:
+    PASSWORD = "hunter2"
+
+    @staticmethod
+    def locked():
+        return Tsk.from_file(fixture_path("locked-key.asc"))
+
+    @staticmethod
+    def callback(*passwords):
+        calls = []
+
+        def password_callback(key_info):
+            calls.append(key_info)
+            return passwords[key_info.attempt - 1]
+
+        return calls, password_callback
+
+    def test_key_info(self):
+        tsk = self.locked()
+        calls, callback = self.callback(self.PASSWORD)
+        signed = sign(tsk.signer(password_callback=callback), b"hello")
+        result = verify(signed, lambda key_ids: [tsk.extract_certificate()])
+        assert result.bytes == b"hello"
+        assert len(calls) == 1
+        assert calls[0].certificate == tsk.extract_certificate().fingerprint
+        assert calls[0].user_id == "Locked <locked@example.com>"
+        assert calls[0].attempt == 1
+
+    def test_decrypt(self):
+        tsk = self.locked()
+        encrypted = encrypt(b"hello", recipients=[tsk.extract_certificate()])
+        calls, callback = self.callback(self.PASSWORD)
+        decryptor = tsk.decryptor(password_callback=callback)
+        assert calls == []
+        assert decrypt(encrypted, decryptor=decryptor).bytes == b"hello"
+        assert [info.attempt for info in calls] == [1]
+
+    def test_not_asked_for_other_messages(self):
+        tsk = self.locked()
+        other = Tsk.generate("Other <other@example.com>")
+        encrypted = encrypt(b"hello", recipients=[other.extract_certificate()])
+        calls, callback = self.callback(self.PASSWORD)
+        decryptors = [tsk.decryptor(password_callback=callback), other]
+        assert decrypt(encrypted, decryptor=decryptors).bytes == b"hello"
+        assert calls == []
+
+    def test_retry(self):
+        tsk = self.locked()
+        encrypted = encrypt(b"hello", recipients=[tsk.extract_certificate()])
+        calls, callback = self.callback("wrong", self.PASSWORD)
+        decryptor = tsk.decryptor(password_callback=callback)
+        assert decrypt(encrypted, decryptor=decryptor).bytes == b"hello"
+        assert [info.attempt for info in calls] == [1, 2]
+
+    def test_cancel(self):
+        tsk = self.locked()
+        calls, callback = self.callback(None)
+        signer = tsk.signer(password_callback=callback)
+        with pytest.raises(Exception):
+            sign(signer, b"hello")
+        assert len(calls) == 1
+
+
+class TestSkeskPasswordCallback:
+    @staticmethod
+    def callback(*passwords):
+        calls = []
+
+        def password_callback(attempt, skesks):
+            calls.append((attempt, skesks))
+            return passwords[attempt - 1]
+
+        return calls, password_callback
+
+    def test_prompt(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"])
+        calls, callback = self.callback("sekrit")
+        assert decrypt(encrypted, password_callback=callback).bytes == b"hello"
+        assert len(calls) == 1
+        attempt, [info] = calls[0]
+        assert attempt == 1
+        assert info.version == 4
+        assert info.s2k == S2K.Iterated
+        assert info.s2k_hash_bytes is not None
+        assert info.argon2_passes is None
+
+    def test_seipdv2(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"], aead=AEADAlgorithm.OCB)
+        calls, callback = self.callback("sekrit")
+        assert decrypt(encrypted, password_callback=callback).bytes == b"hello"
+        [info] = calls[0][1]
+        assert info.version == 6
+        assert info.aead == AEADAlgorithm.OCB
+
+    def test_retry(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"])
+        calls, callback = self.callback("wrong", "sekrit")
+        assert decrypt(encrypted, password_callback=callback).bytes == b"hello"
+        assert [attempt for attempt, _ in calls] == [1, 2]
+
+    def test_cancel(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"])
+        calls, callback = self.callback(None)
+        with pytest.raises(Exception, match="cancelled"):
+            decrypt(encrypted, password_callback=callback)
+        assert len(calls) == 1
+
+    def test_all_skesks(self):
+        encrypted = encrypt(b"hello", passwords=["first", "second"])
+        calls, callback = self.callback("second")
+        assert decrypt(encrypted, password_callback=callback).bytes == b"hello"
+        assert len(calls) == 1
+        assert len(calls[0][1]) == 2
+
+    def test_not_asked_when_key_decrypts(self):
+        tsk = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            b"hello", recipients=[tsk.extract_certificate()], passwords=["sekrit"]
+        )
+        calls, callback = self.callback("sekrit")
+        decrypted = decrypt(encrypted, decryptor=tsk, password_callback=callback)
+        assert decrypted.bytes == b"hello"
+        assert calls == []
+
+    def test_open_decrypt(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"])
+        _, callback = self.callback("sekrit")
+        with open_decrypt(io.BytesIO(encrypted), password_callback=callback) as reader:
+            assert reader.read() == b"hello"
+
+
+class TestInspectMessage:
+    def test_recipients(self):
+        tsk = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted, report = encrypt(
+            b"hello", recipients=[tsk.extract_certificate()], report=True
+        )
+        info = inspect_message(encrypted)
+        assert info.armor == ArmorKind.Message
+        assert info.encrypted
+        assert info.seipd_version == report.seipd_version
+        assert len(info.recipients) == 1
+        recipient = info.recipients[0]
+        assert not recipient.wildcard
+        assert report.recipients[0].subkeys[0].endswith(recipient.key_id)
+        assert recipient.algorithm == PublicKeyAlgorithm.ECDH
+        assert info.skesks == []
+        assert not info.signed
+
+    def test_hidden_recipient_and_password(self):
+        tsk = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            b"hello",
+            hidden_recipients=[tsk.extract_certificate()],
+            passwords=["sekrit"],
+            armor=False,
+        )
+        info = inspect_message(encrypted)
+        assert info.armor is None
+        assert info.recipients[0].wildcard
+        assert info.recipients[0].key_id is None
+        assert len(info.skesks) == 1
+        assert info.skesks[0].s2k == S2K.Iterated
+        assert repr(info.skesks[0]).endswith(" s2k=Iterated>")
+
+    def test_seipdv2(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"], aead=AEADAlgorithm.OCB)
+        info = inspect_message(encrypted)
+        assert info.seipd_version == 2
+        assert info.aead == AEADAlgorithm.OCB
+        assert info.cipher is not None
+
+    def test_signed(self):
+        tsk = Tsk.generate("Signer <signer@example.com>")
+        signed = sign(tsk.signer(), b"hello", signers=[Tsk.generate().signer()])
+        info = inspect_message(signed)
+        assert not info.encrypted
+        assert info.signed
+        assert info.signatures == 2
+        assert not info.compressed
+
+    def test_compressed(self):
+        tsk = Tsk.generate("Signer <signer@example.com>")
+        signed = sign(tsk.signer(), b"hello", compression=CompressionAlgorithm.Zip)
+        info = inspect_message(signed)
+        assert info.compressed
+        assert info.compression == CompressionAlgorithm.Zip
+        # The signatures are inside the compressed data.
+        assert not info.signed
+
+    def test_file(self, tmp_path):
+        path = tmp_path / "message.pgp"
+        path.write_bytes(encrypt(b"hello", passwords=["sekrit"]))
+        assert inspect_message(path).encrypted
+        with open(path, "rb") as message:
+            assert inspect_message(message).encrypted
+
+
+class TestDecryptedDetails:
+    def test_decrypted_with_key(self):
+        tsk = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted, report = encrypt(
+            b"hello", recipients=[tsk.extract_certificate()], report=True
+        )
+        decrypted = decrypt(encrypted, decryptor=tsk.decryptor())
+        assert decrypted.decryption_method == "key"
+        assert decrypted.decryption_key == report.recipients[0].subkeys[0]
+        assert decrypted.cipher == report.cipher
+        assert decrypted.aead == report.aead
+        assert decrypted.seipd_version == report.seipd_version
+        assert decrypted.compression is None
+        assert [layer.kind for layer in decrypted.layers] == ["encryption"]
+
+    def test_decrypted_with_password(self):
+        encrypted = encrypt(b"hello", passwords=["sekrit"], aead=AEADAlgorithm.OCB)
+        decrypted = decrypt(encrypted, passwords=["sekrit"], export_session_key=True)
+        assert decrypted.decryption_method == "password"
+        assert decrypted.decryption_key is None
+        assert decrypted.seipd_version == 2
+        assert decrypted.aead == AEADAlgorithm.OCB
+
+        session_keys = [decrypted.session_key]
+        decrypted = decrypt(encrypted, session_keys=session_keys)
+        assert decrypted.decryption_method == "session_key"
+
+    def test_layers(self):
+        sender = Tsk.generate("Sender <sender@example.com>")
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(
+            b"hello",
+            recipients=[receiver.extract_certificate()],
+            signer=sender.signer(),
+            compression=CompressionAlgorithm.Zip,
+            filename="hello.txt",
+        )
+        decrypted = decrypt(
+            encrypted,
+            decryptor=receiver,
+            store=lambda key_ids: [sender.extract_certificate()],
+        )
+        kinds = [layer.kind for layer in decrypted.layers]
+        assert kinds == ["encryption", "compression", "signature_group"]
+        assert decrypted.layers[1].compression == CompressionAlgorithm.Zip
+        assert decrypted.compression == CompressionAlgorithm.Zip
+        signatures = decrypted.layers[2]
+        sender_fingerprint = sender.extract_certificate().fingerprint
+        assert signatures.valid_sigs[0].certificate == sender_fingerprint
+        assert signatures.errors == []
+        assert decrypted.filename == "hello.txt"
+
+    def test_verified(self):
+        tsk = Tsk.generate("Signer <signer@example.com>")
+        signed = sign(tsk.signer(), b"hello")
+        verified = verify(signed, lambda key_ids: [tsk.extract_certificate()])
+        assert verified.decryption_method is None
+        assert verified.cipher is None
+        assert [layer.kind for layer in verified.layers] == ["signature_group"]
+
+
+class TestSignaturePolicy:
+    @staticmethod
+    def store(*tsks):
+        return lambda key_ids: [tsk.extract_certificate() for tsk in tsks]
+
+    def test_signers(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        bob = Tsk.generate("Bob <bob@example.com>")
+        signed = sign(alice.signer(), b"hello")
+        store = self.store(alice, bob)
+
+        policy = SignaturePolicy(signers=[alice.extract_certificate().fingerprint])
+        assert verify(signed, store, require_signatures=policy).bytes == b"hello"
+
+        policy = SignaturePolicy(signers=[bob.extract_certificate().fingerprint])
+        with pytest.raises(Exception, match="0 of 1 required"):
+            verify(signed, store, require_signatures=policy)
+
+    def test_signing_key(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        signed = sign(alice.signer(), b"hello")
+        store = self.store(alice)
+        signing_key = verify(signed, store).valid_sigs[0].signing_key
+        policy = SignaturePolicy(signers=[signing_key.upper()])
+        assert verify(signed, store, require_signatures=policy).bytes == b"hello"
+
+    def test_min_valid(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        bob = Tsk.generate("Bob <bob@example.com>")
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        store = self.store(alice, bob)
+        policy = SignaturePolicy(min_valid=2)
+
+        encrypted = encrypt(
+            b"hello",
+            recipients=[receiver.extract_certificate()],
+            signers=[alice.signer(), bob.signer()],
+        )
+        decrypted = decrypt(
+            encrypted, decryptor=receiver, store=store, require_signatures=policy
+        )
+        assert len(decrypted.valid_sigs) == 2
+
+        encrypted = encrypt(
+            b"hello",
+            recipients=[receiver.extract_certificate()],
+            signer=alice.signer(),
+        )
+        with pytest.raises(Exception, match="1 of 2 required"):
+            decrypt(
+                encrypted, decryptor=receiver, store=store, require_signatures=policy
+            )
+
+    def test_min_valid_counts_certificates(self):
+        alice = Tsk.generate("Alice <alice@example.com>")
+        signed = sign(alice.signer(), b"hello", signers=[alice.signer()])
+        store = self.store(alice)
+        assert len(verify(signed, store).valid_sigs) == 2
+
+        policy = SignaturePolicy(min_valid=2)
+        with pytest.raises(Exception, match="1 of 2 required"):
+            verify(signed, store, require_signatures=policy)
+
+    def test_min_valid_zero(self):
+        with pytest.raises(ValueError, match="at least 1"):
+            SignaturePolicy(min_valid=0)
+
+    @pytest.mark.parametrize("signer", ["not a fingerprint", "abcd", ""])
+    def test_invalid_signer(self, signer):
+        with pytest.raises(ValueError, match="Invalid fingerprint"):
+            SignaturePolicy(signers=[signer])
+
+    def test_signer_normalized(self):
+        fingerprint = Tsk.generate().extract_certificate().fingerprint
+        spaced = " ".join(
+            fingerprint[i : i + 4] for i in range(0, len(fingerprint), 4)
+        ).upper()
+        assert SignaturePolicy(signers=[spaced]).signers == [fingerprint]
+
+    def test_allow_unsigned(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(b"hello", recipients=[receiver.extract_certificate()])
+        store = self.store()
+        with pytest.raises(Exception, match="no valid signatures"):
+            decrypt(encrypted, decryptor=receiver, store=store)
+
+        policy = SignaturePolicy(allow_unsigned=True)
+        decrypted = decrypt(
+            encrypted, decryptor=receiver, store=store, require_signatures=policy
+        )
+        assert decrypted.bytes == b"hello"
+        assert decrypted.valid_sigs == []
+
+    def test_requires_store(self):
+        receiver = Tsk.generate("Receiver <receiver@example.com>")
+        encrypted = encrypt(b"hello", recipients=[receiver.extract_certificate()])
+        with pytest.raises(Exception, match="store"):
+            decrypt(encrypted, decryptor=receiver, require_signatures=SignaturePolicy())
+
+
+class TestNestedMessages:
+    # Both messages were made by stacking sequoia's streaming writers: a
+    # signature by the outer signer around an encryption or compression
+    # layer, which holds a signature by the inner signer.
+    signers = Cert.split_file(fixture_path("nested-signers.asc"))
+    inner, outer = (signer.fingerprint for signer in signers)
+
+    def store(self, key_ids):
+        return self.signers
+
+    def test_sign_encrypt_sign(self):
+        message = open(fixture_path("sign-encrypt-sign.asc"), "rb").read()
+        recipient = Tsk.from_file(fixture_path("nested-recipient.asc"))
+        decrypted = decrypt(message, decryptor=recipient, store=self.store)
+        assert decrypted.bytes == b"sign-encrypt-sign"
+        kinds = [layer.kind for layer in decrypted.layers]
+        assert kinds == ["signature_group", "encryption", "signature_group"]
+        outer_sigs, _, inner_sigs = (layer.valid_sigs for layer in decrypted.layers)
+        assert [sig.certificate for sig in outer_sigs] == [self.outer]
+        assert [sig.certificate for sig in inner_sigs] == [self.inner]
+        # signatures outside of the encryption do not sign the plaintext
+        assert [sig.certificate for sig in decrypted.valid_sigs] == [self.inner]
+
+    def test_outer_signature_does_not_count(self):
+        message = open(fixture_path("sign-encrypt-sign.asc"), "rb").read()
+        recipient = Tsk.from_file(fixture_path("nested-recipient.asc"))
+        with pytest.raises(Exception, match="0 of 1 required"):
+            decrypt(
+                message,
+                decryptor=recipient,
+                store=self.store,
+                require_signatures=SignaturePolicy(signers=[self.outer]),
+            )
+
+    def test_nested_signatures(self):
+        message = open(fixture_path("nested-signatures.asc"), "rb").read()
+        policy = SignaturePolicy(min_valid=2)
+        verified = verify(message, self.store, require_signatures=policy)
+        assert verified.bytes == b"nested signatures"
+        kinds = [layer.kind for layer in verified.layers]
+        assert kinds == ["signature_group", "compression", "signature_group"]
+        assert verified.compression == CompressionAlgorithm.Zip
+        assert {sig.certificate for sig in verified.valid_sigs} == {
+            self.inner,
+            self.outer,
+        }